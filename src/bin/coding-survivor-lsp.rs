@@ -0,0 +1,8 @@
+use coding_survivor::lsp::run_server;
+
+fn main() {
+    if let Err(error) = run_server() {
+        eprintln!("Language server stopped : {:?}", error);
+        std::process::exit(1);
+    }
+}
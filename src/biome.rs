@@ -0,0 +1,161 @@
+use std::ops::Range;
+
+use raylib::color::Color;
+
+use crate::game_state::Tile;
+
+/// Frequency of the temperature and moisture layers, lower than the terrain one so that a biome
+/// spans many tiles.
+pub const BIOME_NOISE_SCALE: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Plains,
+    Forest,
+    Desert,
+    Volcanic,
+    Lake,
+}
+
+/// How a biome turns the terrain and tree noise into tiles.
+pub struct BiomeProfile {
+    /// Terrain below this value is a wall.
+    pub wall_below: f64,
+    /// Terrain above this value is water.
+    pub water_above: f64,
+    /// Terrain values turning into lava.
+    pub lava: Option<Range<f64>>,
+    /// Tree noise below this value grows a tree on ground, the higher the denser.
+    pub tree_below: f64,
+    /// Lowers the vein threshold, making veins of the biome larger.
+    pub ore_richness: f64,
+    /// Ores the veins may hold, with their weight.
+    pub ores: &'static [(Tile, u32)],
+    /// Colour the tiles of the biome are tinted with.
+    pub tint: Color,
+}
+
+static PLAINS: BiomeProfile = BiomeProfile {
+    wall_below: 0.0,
+    water_above: 0.8,
+    lava: Some(0.5..0.505),
+    tree_below: -0.5,
+    ore_richness: 0.0,
+    ores: &[(Tile::Bronze, 50), (Tile::Silver, 20), (Tile::Gold, 15), (Tile::Mytril, 10), (Tile::Demonite, 5)],
+    tint: Color::new(255, 255, 255, 255),
+};
+
+static FOREST: BiomeProfile = BiomeProfile {
+    wall_below: -0.1,
+    water_above: 0.75,
+    lava: None,
+    tree_below: -0.2,
+    ore_richness: 0.0,
+    ores: &[(Tile::Bronze, 60), (Tile::Silver, 25), (Tile::Gold, 10), (Tile::Mytril, 5)],
+    tint: Color::new(200, 255, 200, 255),
+};
+
+static DESERT: BiomeProfile = BiomeProfile {
+    wall_below: 0.1,
+    water_above: 0.9,
+    lava: Some(0.5..0.51),
+    tree_below: -0.75,
+    ore_richness: 0.03,
+    ores: &[(Tile::Bronze, 30), (Tile::Silver, 30), (Tile::Gold, 35), (Tile::Mytril, 5)],
+    tint: Color::new(255, 235, 180, 255),
+};
+
+static VOLCANIC: BiomeProfile = BiomeProfile {
+    wall_below: 0.1,
+    water_above: f64::MAX,
+    lava: Some(0.3..0.4),
+    tree_below: f64::MIN,
+    ore_richness: 0.05,
+    ores: &[(Tile::Silver, 20), (Tile::Gold, 30), (Tile::Mytril, 30), (Tile::Demonite, 20)],
+    tint: Color::new(255, 190, 170, 255),
+};
+
+static LAKE: BiomeProfile = BiomeProfile {
+    wall_below: -0.2,
+    water_above: 0.2,
+    lava: None,
+    tree_below: -0.4,
+    ore_richness: -0.05,
+    ores: &[(Tile::Bronze, 50), (Tile::Silver, 40), (Tile::Gold, 10)],
+    tint: Color::new(190, 220, 255, 255),
+};
+
+impl Biome {
+    /// Picks the biome from the temperature and moisture noise values at a tile.
+    pub fn from_climate(temperature: f64, moisture: f64) -> Biome {
+        if temperature > 0.35 {
+            Biome::Volcanic
+        } else if temperature > 0.1 && moisture < 0.0 {
+            Biome::Desert
+        } else if moisture > 0.3 {
+            Biome::Lake
+        } else if moisture > 0.0 {
+            Biome::Forest
+        } else {
+            Biome::Plains
+        }
+    }
+
+    pub fn profile(&self) -> &'static BiomeProfile {
+        match self {
+            Biome::Plains => &PLAINS,
+            Biome::Forest => &FOREST,
+            Biome::Desert => &DESERT,
+            Biome::Volcanic => &VOLCANIC,
+            Biome::Lake => &LAKE,
+        }
+    }
+
+    /// Name of the biome as returned to scripts.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Biome::Plains => "plains",
+            Biome::Forest => "forest",
+            Biome::Desert => "desert",
+            Biome::Volcanic => "volcanic",
+            Biome::Lake => "lake",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Biome;
+    use crate::world::World;
+
+    /// Positions spread over several biomes, the climate noise changing over about a hundred tiles.
+    fn sampled_positions() -> impl Iterator<Item = (i32, i32)> {
+        (-4..4).flat_map(|x| (-4..4).map(move |y| (x * 97, y * 89)))
+    }
+
+    #[test]
+    fn picks_the_same_biomes_for_a_seed() {
+        let mut world = World::new(7, false);
+        let mut same_seed_world = World::new(7, false);
+        let mut other_seed_world = World::new(8, false);
+        let biomes: Vec<Biome> = sampled_positions().map(|(x, y)| world.biome(x, y)).collect();
+        let same_seed_biomes: Vec<Biome> = sampled_positions().map(|(x, y)| same_seed_world.biome(x, y)).collect();
+        let other_seed_biomes: Vec<Biome> = sampled_positions().map(|(x, y)| other_seed_world.biome(x, y)).collect();
+        assert!(biomes.iter().any(|biome| *biome != biomes[0]), "Positions should span several biomes");
+        assert_eq!(biomes, same_seed_biomes);
+        assert_ne!(biomes, other_seed_biomes);
+    }
+
+    #[test]
+    fn maps_each_climate_to_its_biome() {
+        assert_eq!(Biome::from_climate(1.0, 1.0), Biome::Volcanic);
+        assert_eq!(Biome::from_climate(1.0, -1.0), Biome::Volcanic);
+        assert_eq!(Biome::from_climate(-1.0, 1.0), Biome::Lake);
+        assert_eq!(Biome::from_climate(-1.0, -1.0), Biome::Plains);
+        // Warm only makes a desert when dry, moisture then decides between lake, forest and plains
+        assert_eq!(Biome::from_climate(0.2, -0.5), Biome::Desert);
+        assert_eq!(Biome::from_climate(0.2, 0.5), Biome::Lake);
+        assert_eq!(Biome::from_climate(0.0, 0.1), Biome::Forest);
+        assert_eq!(Biome::from_climate(0.0, -0.1), Biome::Plains);
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+use crate::editor::formatter::{format_script, FormatError};
+
+const CHECK_FLAG: &str = "--check";
+
+#[derive(Debug)]
+enum FmtError {
+    Io(io::Error),
+    Format(FormatError),
+}
+
+/// Formats the given script files in place, or stdin to stdout when no file is given.
+/// With `--check`, files are left untouched and the command fails if any is not formatted.
+pub fn fmt_command(arguments: &[String]) -> i32 {
+    let check = arguments.iter().any(|argument| argument == CHECK_FLAG);
+    let paths: Vec<&String> = arguments.iter().filter(|argument| *argument != CHECK_FLAG).collect();
+    if paths.is_empty() {
+        return match format_stdin() {
+            Ok(formatted) => {
+                print!("{}", formatted);
+                0
+            }
+            Err(error) => {
+                eprintln!("<stdin> : {}", error_message(&error));
+                1
+            }
+        };
+    }
+    let mut exit_code = 0;
+    for path in paths {
+        match format_file(path, check) {
+            Ok(true) => (),
+            Ok(false) => {
+                println!("{} is not formatted", path);
+                exit_code = 1;
+            }
+            Err(error) => {
+                eprintln!("{} : {}", path, error_message(&error));
+                exit_code = 1;
+            }
+        }
+    }
+    exit_code
+}
+
+fn format_stdin() -> Result<String, FmtError> {
+    let mut source = String::new();
+    io::stdin().read_to_string(&mut source).map_err(FmtError::Io)?;
+    format_script(&source).map_err(FmtError::Format)
+}
+
+/// Returns whether the file was already formatted.
+fn format_file(path: &str, check: bool) -> Result<bool, FmtError> {
+    let source = fs::read_to_string(path).map_err(FmtError::Io)?;
+    let formatted = format_script(&source).map_err(FmtError::Format)?;
+    if formatted == source {
+        return Ok(true);
+    }
+    if !check {
+        fs::write(path, formatted).map_err(FmtError::Io)?;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+fn error_message(error: &FmtError) -> String {
+    match error {
+        FmtError::Io(error) => error.to_string(),
+        FmtError::Format(error) => format!("Invalid script : {:?}", error),
+    }
+}
@@ -0,0 +1,43 @@
+pub mod fmt;
+pub mod run;
+
+const USAGE: &str = "Usage : coding-survivor [--seed N | fmt [--check] [files...] | run --script file [--seed N] [--ticks T] [--ore-stats]]";
+
+#[derive(Debug)]
+pub enum GameOptionError {
+    MissingSeed,
+    InvalidSeed(String),
+    UnknownArgument(String),
+}
+
+/// Runs a command line subcommand instead of the game, returning the process exit code.
+pub fn run_command(command: &str, arguments: &[String]) -> i32 {
+    match command {
+        "fmt" => fmt::fmt_command(arguments),
+        "run" => run::run_command(arguments),
+        _ => {
+            eprintln!("Unknown command {}\n{}", command, USAGE);
+            2
+        }
+    }
+}
+
+/// Seed of the world to play, given with `--seed N` when the game is started without subcommand.
+/// A random one is picked otherwise.
+pub fn game_seed(arguments: &[String]) -> Result<u64, GameOptionError> {
+    match arguments {
+        [] => Ok(rand::random()),
+        [flag] if flag == "--seed" => Err(GameOptionError::MissingSeed),
+        [flag, seed] if flag == "--seed" => seed.parse().map_err(|_| GameOptionError::InvalidSeed(seed.clone())),
+        [argument, ..] => Err(GameOptionError::UnknownArgument(argument.clone())),
+    }
+}
+
+pub fn print_game_option_error(error: &GameOptionError) {
+    let message = match error {
+        GameOptionError::MissingSeed => "Missing value after --seed".to_string(),
+        GameOptionError::InvalidSeed(seed) => format!("Invalid seed {}, expected a number up to {}", seed, u64::MAX),
+        GameOptionError::UnknownArgument(argument) => format!("Unknown argument {}", argument),
+    };
+    eprintln!("{}\n{}", message, USAGE);
+}
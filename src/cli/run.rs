@@ -0,0 +1,229 @@
+use std::{any::Any, fs, io, thread};
+
+use serde_json::{json, Value};
+
+use crate::{
+    editor::{
+        grammar::{resolve_ast, Ast, AstParseError, Expression, Function, Operation, Primary, Unary},
+        interpreter::interpret_expression,
+        keyboard::{interpreter_error_message, SCRIPT_THREAD_STACK_SIZE},
+        limits::start_run,
+        tokenizer::{get_prompt_tokens, TokenizerError},
+    },
+    game_state::{get_tile_string, init_map, process_chunks, process_clock, process_creatures, process_player_breaking, process_player_health, process_player_path, process_player_position, EDITOR_OUTPUT, MAP_STATE},
+};
+
+const TICK_RATE: f32 = 60.0;
+const DEFAULT_TICKS: u64 = 600;
+const USAGE: &str = "Usage : coding-survivor run --script file [--seed N] [--ticks T] [--ore-stats]";
+
+#[derive(Debug)]
+enum RunError {
+    MissingScript,
+    MissingValue(String),
+    InvalidValue(String, String),
+    UnknownArgument(String),
+    Io(io::Error),
+    TokenizerError(TokenizerError),
+    AstParseError(AstParseError),
+}
+
+struct RunOptions {
+    seed: u64,
+    script: String,
+    ticks: u64,
+    /// Adds ore counts by distance from the spawn to the summary, sampling a large area.
+    ore_statistics: bool,
+}
+
+/// Runs a script against a generated world without opening a window, then prints a JSON summary
+/// of the final state. One top level statement is executed per tick, before the game logic of
+/// that tick, and the script stops at its first error like in the editor.
+pub fn run_command(arguments: &[String]) -> i32 {
+    let options = match parse_options(arguments) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n{}", error_message(&error), USAGE);
+            return 2;
+        }
+    };
+    let ast = match load_script(&options.script) {
+        Ok(ast) => ast,
+        Err(error) => {
+            eprintln!("{} : {}", options.script, error_message(&error));
+            return 1;
+        }
+    };
+    let (seed, ticks) = (options.seed, options.ticks);
+    let summary = thread::Builder::new()
+        .stack_size(SCRIPT_THREAD_STACK_SIZE)
+        .spawn(move || simulate(&ast, &options))
+        .expect("Failed to start simulation thread")
+        .join()
+        .unwrap_or_else(|panic| panic_summary(seed, ticks, panic));
+    println!("{}", summary);
+    let has_errors = summary["errors"].as_array().is_some_and(|errors| !errors.is_empty());
+    if has_errors { 1 } else { 0 }
+}
+
+fn parse_options(arguments: &[String]) -> Result<RunOptions, RunError> {
+    let mut seed = None;
+    let mut script = None;
+    let mut ticks = DEFAULT_TICKS;
+    let mut ore_statistics = false;
+    let mut arguments = arguments.iter();
+    while let Some(flag) = arguments.next() {
+        let value = match flag.as_str() {
+            "--ore-stats" => {
+                ore_statistics = true;
+                continue;
+            }
+            "--seed" | "--script" | "--ticks" => arguments.next().ok_or(RunError::MissingValue(flag.clone()))?,
+            _ => return Err(RunError::UnknownArgument(flag.clone())),
+        };
+        let invalid_value = || RunError::InvalidValue(flag.clone(), value.clone());
+        match flag.as_str() {
+            "--seed" => seed = Some(value.parse().map_err(|_| invalid_value())?),
+            "--ticks" => ticks = value.parse().map_err(|_| invalid_value())?,
+            _ => script = Some(value.clone()),
+        }
+    }
+    let seed = seed.unwrap_or_else(|| {
+        let seed = rand::random();
+        eprintln!("No --seed given, running with seed {}", seed);
+        seed
+    });
+    Ok(RunOptions {
+        seed,
+        script: script.ok_or(RunError::MissingScript)?,
+        ticks,
+        ore_statistics,
+    })
+}
+
+fn load_script(path: &str) -> Result<Ast, RunError> {
+    let source = fs::read_to_string(path).map_err(RunError::Io)?;
+    let tokens = get_prompt_tokens(source).map_err(RunError::TokenizerError)?;
+    resolve_ast(tokens).map_err(RunError::AstParseError)
+}
+
+fn simulate(ast: &Ast, options: &RunOptions) -> Value {
+    // Saved chunks are ignored so that a run only depends on the seed and the script
+    init_map(options.seed, false);
+    start_run();
+    let dt = 1.0 / TICK_RATE;
+    let mut statements = ast
+        .tree
+        .iter()
+        .enumerate()
+        .filter(|(_, expression)| !matches!(expression, Expression::Function(Function::Operation(Operation::Unary(Unary::Primary(Primary::Eof))))))
+        .peekable();
+    let total_statements = statements.clone().count();
+    let mut executed_statements = 0;
+    let mut errors = vec![];
+    for _ in 0..options.ticks {
+        if errors.is_empty() {
+            if let Some((index, expression)) = statements.next() {
+                match interpret_expression(expression) {
+                    Ok(_) => executed_statements += 1,
+                    Err(error) => errors.push(json!({
+                        "line": ast.lines.get(index).copied().unwrap_or_default() + 1,
+                        "message": interpreter_error_message(&error),
+                    })),
+                }
+            }
+        }
+        process_player_path();
+        process_player_position();
+        process_player_breaking(dt);
+        process_player_health(dt);
+        process_clock(dt);
+        process_creatures(dt);
+        process_chunks();
+    }
+    let map_state = MAP_STATE.lock().expect("Failed to get map state");
+    let inventory: Vec<Value> = map_state
+        .player
+        .inventory
+        .iter()
+        .map(|item| json!({ "id": item.id, "number": item.number }))
+        .collect();
+    let creatures: Vec<Value> = map_state
+        .creatures
+        .iter()
+        .map(|creature| json!({
+            "kind": creature.kind.get_name(),
+            "x": creature.position.x,
+            "y": creature.position.y,
+            "health": creature.health,
+        }))
+        .collect();
+    let output: Vec<String> = EDITOR_OUTPUT.lock().expect("Failed to get editor output").drain(..).collect();
+    let mut summary = json!({
+        "seed": options.seed,
+        "ticks": options.ticks,
+        "statements": { "executed": executed_statements, "total": total_statements },
+        "completed": errors.is_empty() && statements.peek().is_none(),
+        "player": {
+            "x": map_state.player.position.x,
+            "y": map_state.player.position.y,
+            "health": map_state.player.health,
+            "hunger": map_state.player.hunger,
+            "thirst": map_state.player.thirst,
+        },
+        "clock": { "day": map_state.clock.day(), "hour": map_state.clock.hour() },
+        "inventory": inventory,
+        "creatures": creatures,
+        "output": output,
+        "errors": errors,
+    });
+    if options.ore_statistics {
+        let ore_bands: Vec<Value> = map_state
+            .world
+            .ore_statistics()
+            .bands
+            .iter()
+            .map(|band| {
+                let ores: serde_json::Map<String, Value> = band
+                    .ores
+                    .iter()
+                    .map(|(ore, count)| (get_tile_string(ore), json!(count)))
+                    .collect();
+                json!({
+                    "distance": [band.distance.start, band.distance.end],
+                    "sampledTiles": band.sampled_tiles,
+                    "walls": band.walls,
+                    "ores": ores,
+                })
+            })
+            .collect();
+        summary["oreStatistics"] = json!(ore_bands);
+    }
+    summary
+}
+
+/// Summary of a simulation that panicked, so that callers still get JSON and a failed run.
+fn panic_summary(seed: u64, ticks: u64, panic: Box<dyn Any + Send>) -> Value {
+    let reason = match panic.downcast_ref::<&str>() {
+        Some(reason) => reason.to_string(),
+        None => panic.downcast_ref::<String>().cloned().unwrap_or_default(),
+    };
+    json!({
+        "seed": seed,
+        "ticks": ticks,
+        "completed": false,
+        "errors": [{ "message": format!("Simulation panicked : {}", reason) }],
+    })
+}
+
+fn error_message(error: &RunError) -> String {
+    match error {
+        RunError::MissingScript => "Missing --script option".to_string(),
+        RunError::MissingValue(flag) => format!("Missing value after {}", flag),
+        RunError::InvalidValue(flag, value) => format!("Invalid value {} for {}", value, flag),
+        RunError::UnknownArgument(argument) => format!("Unknown argument {}", argument),
+        RunError::Io(error) => error.to_string(),
+        RunError::TokenizerError(error) => format!("Invalid script : {:?}", error),
+        RunError::AstParseError(error) => format!("Invalid script : {:?}", error),
+    }
+}
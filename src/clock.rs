@@ -0,0 +1,134 @@
+/// Game seconds of a whole day.
+pub const DAY_LENGTH: f32 = 600.0;
+/// Hour of the day when a world starts.
+const START_HOUR: f32 = 8.0;
+const DAWN_START: f32 = 5.0;
+const DAY_START: f32 = 7.0;
+const DUSK_START: f32 = 18.0;
+const NIGHT_START: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayPhase {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl DayPhase {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            DayPhase::Dawn => "dawn",
+            DayPhase::Day => "day",
+            DayPhase::Dusk => "dusk",
+            DayPhase::Night => "night",
+        }
+    }
+}
+
+pub struct Clock {
+    /// Game seconds since the world started.
+    pub elapsed: f32,
+}
+
+impl Clock {
+    /// Clock of a world that just started.
+    pub const fn start() -> Clock {
+        Clock { elapsed: 0.0 }
+    }
+
+    /// Hour of the current day, from 0 included to 24 excluded.
+    pub fn hour(&self) -> f32 {
+        (START_HOUR + self.elapsed / DAY_LENGTH * 24.0) % 24.0
+    }
+
+    /// Day number, starting at 1.
+    pub fn day(&self) -> u32 {
+        ((START_HOUR / 24.0 + self.elapsed / DAY_LENGTH) as u32) + 1
+    }
+
+    pub fn phase(&self) -> DayPhase {
+        let hour = self.hour();
+        if (DAWN_START..DAY_START).contains(&hour) {
+            DayPhase::Dawn
+        } else if (DAY_START..DUSK_START).contains(&hour) {
+            DayPhase::Day
+        } else if (DUSK_START..NIGHT_START).contains(&hour) {
+            DayPhase::Dusk
+        } else {
+            DayPhase::Night
+        }
+    }
+
+    /// Daylight from 0 at night to 1 during the day, rising at dawn and falling at dusk.
+    pub fn daylight(&self) -> f32 {
+        let hour = self.hour();
+        match self.phase() {
+            DayPhase::Dawn => (hour - DAWN_START) / (DAY_START - DAWN_START),
+            DayPhase::Day => 1.0,
+            DayPhase::Dusk => 1.0 - (hour - DUSK_START) / (NIGHT_START - DUSK_START),
+            DayPhase::Night => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, DayPhase, DAY_LENGTH, START_HOUR};
+
+    /// Clock at the hour of the day, the first day being day 1.
+    fn clock_at(day: u32, hour: f32) -> Clock {
+        let hours = (day - 1) as f32 * 24.0 + hour - START_HOUR;
+        Clock { elapsed: hours / 24.0 * DAY_LENGTH }
+    }
+
+    #[test]
+    fn starts_in_the_morning_of_the_first_day() {
+        let clock = Clock::start();
+        assert_eq!(clock.day(), 1);
+        assert_eq!(clock.hour(), START_HOUR);
+        assert_eq!(clock.phase(), DayPhase::Day);
+    }
+
+    #[test]
+    fn changes_phase_at_each_boundary() {
+        let boundaries = [
+            (5.0, DayPhase::Night, DayPhase::Dawn),
+            (7.0, DayPhase::Dawn, DayPhase::Day),
+            (18.0, DayPhase::Day, DayPhase::Dusk),
+            (20.0, DayPhase::Dusk, DayPhase::Night),
+        ];
+        for (hour, before, after) in boundaries {
+            assert_eq!(clock_at(2, hour - 0.01).phase(), before, "Before {}h", hour);
+            assert_eq!(clock_at(2, hour + 0.01).phase(), after, "After {}h", hour);
+        }
+    }
+
+    #[test]
+    fn wraps_into_the_next_day_at_midnight() {
+        let before_midnight = clock_at(1, 23.99);
+        let after_midnight = clock_at(2, 0.01);
+        assert_eq!(before_midnight.day(), 1);
+        assert_eq!(after_midnight.day(), 2);
+        assert!(after_midnight.hour() < 0.1);
+        assert_eq!(before_midnight.phase(), DayPhase::Night);
+        assert_eq!(after_midnight.phase(), DayPhase::Night);
+        assert_eq!(after_midnight.daylight(), 0.0);
+    }
+
+    #[test]
+    fn daylight_rises_at_dawn_and_falls_at_dusk() {
+        assert_eq!(clock_at(1, 12.0).daylight(), 1.0);
+        assert_eq!(clock_at(1, 22.0).daylight(), 0.0);
+        assert!((clock_at(2, 6.0).daylight() - 0.5).abs() < 0.01);
+        assert!((clock_at(1, 19.0).daylight() - 0.5).abs() < 0.01);
+        // Sampled every game second over two days, across midnight, daylight never jumps
+        let mut previous = Clock::start().daylight();
+        for second in 1..(2.0 * DAY_LENGTH) as u32 {
+            let daylight = Clock { elapsed: second as f32 }.daylight();
+            assert!((0.0..=1.0).contains(&daylight));
+            assert!((daylight - previous).abs() < 0.05, "Daylight jumps at {}s", second);
+            previous = daylight;
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use rand::{rngs::StdRng, Rng};
+use raylib::ffi::Vector2;
+
+use crate::{
+    biome::Biome,
+    clock::DayPhase,
+    game_state::{Direction, MapState, Tile},
+};
+
+/// Seconds between two spawn attempts.
+const SPAWN_INTERVAL: f32 = 4.0;
+const MAX_CREATURES: usize = 8;
+/// Creatures spawn out of sight, between these distances in tiles from the player.
+const SPAWN_MIN_DISTANCE: i32 = 8;
+const SPAWN_MAX_DISTANCE: i32 = 16;
+/// Creatures further than this distance in tiles from the player are removed.
+const DESPAWN_DISTANCE: i32 = 40;
+/// Seconds between two attacks of a creature.
+const CREATURE_ATTACK_DELAY: f32 = 1.0;
+/// Displayed position step of a creature per processed frame.
+const CREATURE_VELOCITY: f32 = 0.125;
+/// Seconds each animation frame of a creature is shown.
+const CREATURE_FRAME_TIME: f32 = 0.2;
+const CREATURE_FRAME_NUMBER: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreatureKind {
+    Wraith,
+    MagmaSlime,
+}
+
+pub const CREATURE_KINDS: [CreatureKind; 2] = [CreatureKind::Wraith, CreatureKind::MagmaSlime];
+
+/// How a kind of creature spawns, moves and fights.
+pub struct CreatureProfile {
+    pub max_health: f32,
+    /// Health removed from the player by one attack.
+    pub damage: f32,
+    /// Seconds between two steps.
+    pub step_delay: f32,
+    /// Distance in tiles from which the player is chased.
+    pub sight: i32,
+    /// Biomes the creature spawns in, any biome when empty.
+    pub biomes: &'static [Biome],
+    /// Whether the creature only spawns at night, and vanishes once the day has come.
+    pub nocturnal: bool,
+}
+
+static WRAITH: CreatureProfile = CreatureProfile {
+    max_health: 30.0,
+    damage: 10.0,
+    step_delay: 0.6,
+    sight: 10,
+    biomes: &[],
+    nocturnal: true,
+};
+
+static MAGMA_SLIME: CreatureProfile = CreatureProfile {
+    max_health: 50.0,
+    damage: 15.0,
+    step_delay: 1.0,
+    sight: 6,
+    biomes: &[Biome::Volcanic],
+    nocturnal: false,
+};
+
+impl CreatureKind {
+    pub fn profile(&self) -> &'static CreatureProfile {
+        match self {
+            CreatureKind::Wraith => &WRAITH,
+            CreatureKind::MagmaSlime => &MAGMA_SLIME,
+        }
+    }
+
+    /// Name of the creature, also used as its texture name.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            CreatureKind::Wraith => "Wraith",
+            CreatureKind::MagmaSlime => "MagmaSlime",
+        }
+    }
+}
+
+pub struct Creature {
+    pub kind: CreatureKind,
+    pub position: Vector2,
+    /// Displayed position, moving towards the tile position.
+    pub previous_position: Vector2,
+    pub health: f32,
+    step_cooldown: f32,
+    attack_cooldown: f32,
+    pub current_frame: u32,
+    frame_time: f32,
+}
+
+impl Creature {
+    pub(crate) fn new(kind: CreatureKind, x: i32, y: i32) -> Creature {
+        let position = Vector2 { x: x as f32, y: y as f32 };
+        Creature {
+            kind,
+            position,
+            previous_position: position,
+            health: kind.profile().max_health,
+            step_cooldown: kind.profile().step_delay,
+            attack_cooldown: 0.0,
+            current_frame: 0,
+            frame_time: 0.0,
+        }
+    }
+
+    fn animate(&mut self, dt: f32) {
+        self.frame_time += dt;
+        if self.frame_time >= CREATURE_FRAME_TIME {
+            self.frame_time = 0.0;
+            self.current_frame = (self.current_frame + 1) % CREATURE_FRAME_NUMBER;
+        }
+        for (previous, current) in [
+            (&mut self.previous_position.x, self.position.x),
+            (&mut self.previous_position.y, self.position.y),
+        ] {
+            if *previous < current {
+                *previous += CREATURE_VELOCITY;
+            } else if *previous > current {
+                *previous -= CREATURE_VELOCITY;
+            }
+        }
+    }
+}
+
+/// Creatures walk where the player does but keep away from hazards.
+fn is_walkable_by_creature(tile: &Tile) -> bool {
+    Tile::is_walkable(tile) && !Tile::is_hazard(tile)
+}
+
+fn distance(a: Vector2, b: Vector2) -> i32 {
+    (a.x - b.x).abs() as i32 + (a.y - b.y).abs() as i32
+}
+
+impl MapState {
+    /// Spawns, moves and despawns creatures over dt seconds, and lets them attack the player.
+    pub fn update_creatures(&mut self, dt: f32) {
+        let Some(mut rng) = self.creature_rng.take() else {
+            return;
+        };
+        let player = self.player.position;
+        let night = self.clock.phase() == DayPhase::Night;
+        let daylight = self.clock.daylight();
+        self.creatures.retain(|creature| {
+            distance(creature.position, player) <= DESPAWN_DISTANCE
+                && !(creature.kind.profile().nocturnal && daylight >= 1.0)
+        });
+        self.creature_spawn_cooldown -= dt;
+        if self.creature_spawn_cooldown <= 0.0 {
+            self.creature_spawn_cooldown = SPAWN_INTERVAL;
+            self.try_spawn_creature(&mut rng, night);
+        }
+        for index in 0..self.creatures.len() {
+            self.update_creature(index, dt, &mut rng);
+        }
+        self.creature_rng = Some(rng);
+    }
+
+    fn try_spawn_creature(&mut self, rng: &mut StdRng, night: bool) {
+        if self.creatures.len() >= MAX_CREATURES {
+            return;
+        }
+        let player = self.player.position;
+        let distance = rng.random_range(SPAWN_MIN_DISTANCE..=SPAWN_MAX_DISTANCE);
+        let dx = rng.random_range(-distance..=distance);
+        let dy = (distance - dx.abs()) * if rng.random() { 1 } else { -1 };
+        let (x, y) = (player.x as i32 + dx, player.y as i32 + dy);
+        if !is_walkable_by_creature(self.world.tile(x, y)) || self.creature_at(x, y).is_some() {
+            return;
+        }
+        let biome = self.world.biome(x, y);
+        let candidates: Vec<CreatureKind> = CREATURE_KINDS
+            .into_iter()
+            .filter(|kind| {
+                let profile = kind.profile();
+                (!profile.nocturnal || night) && (profile.biomes.is_empty() || profile.biomes.contains(&biome))
+            })
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+        let kind = candidates[rng.random_range(0..candidates.len())];
+        self.creatures.push(Creature::new(kind, x, y));
+    }
+
+    /// Chases the player in sight or wanders, and attacks the player once next to them.
+    fn update_creature(&mut self, index: usize, dt: f32, rng: &mut StdRng) {
+        let player = self.player.position;
+        let creature = &mut self.creatures[index];
+        let profile = creature.kind.profile();
+        creature.animate(dt);
+        creature.attack_cooldown = (creature.attack_cooldown - dt).max(0.0);
+        creature.step_cooldown -= dt;
+        let player_distance = distance(creature.position, player);
+        if player_distance <= 1 && !self.player.is_dead() {
+            if creature.attack_cooldown <= 0.0 {
+                creature.attack_cooldown = CREATURE_ATTACK_DELAY;
+                self.damage_player(profile.damage);
+            }
+            return;
+        }
+        if creature.step_cooldown > 0.0 {
+            return;
+        }
+        creature.step_cooldown = profile.step_delay;
+        let position = creature.position;
+        let directions = if player_distance <= profile.sight && !self.player.is_dead() {
+            let (dx, dy) = (player.x - position.x, player.y - position.y);
+            let horizontal = if dx > 0.0 { Direction::Right } else { Direction::Left };
+            let vertical = if dy > 0.0 { Direction::Down } else { Direction::Up };
+            if dx.abs() >= dy.abs() {
+                vec![horizontal, vertical]
+            } else {
+                vec![vertical, horizontal]
+            }
+        } else {
+            match rng.random_range(0..6) {
+                0 => vec![Direction::Up],
+                1 => vec![Direction::Down],
+                2 => vec![Direction::Left],
+                3 => vec![Direction::Right],
+                _ => vec![],
+            }
+        };
+        for direction in directions {
+            let next = direction.next_to(position);
+            let (x, y) = (next.x as i32, next.y as i32);
+            let occupied = self.creature_at(x, y).is_some() || (next.x == player.x && next.y == player.y);
+            if !occupied && is_walkable_by_creature(self.world.tile(x, y)) {
+                self.creatures[index].position = next;
+                return;
+            }
+        }
+    }
+
+    /// Index of the creature standing on the tile.
+    pub fn creature_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.creatures
+            .iter()
+            .position(|creature| creature.position.x as i32 == x && creature.position.y as i32 == y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+    use raylib::ffi::Vector2;
+
+    use super::{Creature, CreatureKind, CREATURE_ATTACK_DELAY};
+    use crate::{
+        game_state::{MapState, Tile, MAX_HEALTH},
+        world::World,
+    };
+
+    /// Map with the player at the origin of a ground area, and one magma slime, which hunts by day.
+    fn map_with_slime(x: i32, y: i32) -> MapState {
+        let mut map = MapState::empty();
+        map.world = World::new(1, false);
+        for tile_y in -12..=12 {
+            for tile_x in -12..=30 {
+                map.world.set_tile(tile_x, tile_y, Tile::Ground);
+            }
+        }
+        map.player.position = Vector2 { x: 0.0, y: 0.0 };
+        map.creature_rng = Some(StdRng::seed_from_u64(7));
+        map.creature_spawn_cooldown = f32::MAX;
+        map.creatures = vec![Creature::new(CreatureKind::MagmaSlime, x, y)];
+        map
+    }
+
+    fn slime_position(map: &MapState) -> (f32, f32) {
+        (map.creatures[0].position.x, map.creatures[0].position.y)
+    }
+
+    /// Updates creatures for the time of one step of a magma slime.
+    fn step(map: &mut MapState) {
+        map.update_creatures(CreatureKind::MagmaSlime.profile().step_delay);
+    }
+
+    #[test]
+    fn chases_the_player_in_sight_until_next_to_them() {
+        let mut map = map_with_slime(4, 1);
+        step(&mut map);
+        assert_eq!(slime_position(&map), (3.0, 1.0));
+        for _ in 0..5 {
+            step(&mut map);
+        }
+        let (x, y) = slime_position(&map);
+        assert_eq!(x.abs() + y.abs(), 1.0);
+    }
+
+    #[test]
+    fn goes_around_walls_and_hazards() {
+        let mut map = map_with_slime(3, 1);
+        map.world.set_tile(2, 1, Tile::Wall);
+        step(&mut map);
+        assert_eq!(slime_position(&map), (3.0, 0.0));
+        map.world.set_tile(2, 0, Tile::Lava);
+        map.world.set_tile(3, -1, Tile::Lava);
+        step(&mut map);
+        assert_eq!(slime_position(&map), (3.0, 0.0));
+    }
+
+    #[test]
+    fn wanders_the_same_way_with_the_same_seed() {
+        let mut map = map_with_slime(20, 0);
+        let mut other_map = map_with_slime(20, 0);
+        let mut positions = vec![];
+        for _ in 0..20 {
+            step(&mut map);
+            step(&mut other_map);
+            assert_eq!(slime_position(&map), slime_position(&other_map));
+            positions.push(slime_position(&map));
+        }
+        assert!(positions.iter().any(|position| *position != (20.0, 0.0)));
+    }
+
+    #[test]
+    fn hurts_the_player_on_contact_once_per_attack_delay() {
+        let damage = CreatureKind::MagmaSlime.profile().damage;
+        let mut map = map_with_slime(1, 0);
+        map.update_creatures(0.1);
+        assert_eq!(map.player.health, MAX_HEALTH - damage);
+        map.update_creatures(CREATURE_ATTACK_DELAY / 2.0);
+        assert_eq!(map.player.health, MAX_HEALTH - damage);
+        map.update_creatures(CREATURE_ATTACK_DELAY / 2.0);
+        assert_eq!(map.player.health, MAX_HEALTH - 2.0 * damage);
+        assert_eq!(slime_position(&map), (1.0, 0.0));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::game_state::EditorState;
+
+use super::{
+    functions::FUNCTIONS,
+    tokenizer::{get_current_token, get_current_token_start},
+};
+
+pub struct SignatureHelp {
+    pub name: String,
+    pub arguments: Vec<String>,
+    pub returns: String,
+    pub doc: String,
+    pub active_argument: usize,
+}
+
+pub struct Completion {
+    pub candidates: Vec<String>,
+    pub index: usize,
+    pub token_start: usize,
+}
+
+pub fn get_completions(current_token: &str) -> Vec<String> {
+    let functions = FUNCTIONS.lock().expect("Failed to resolve functions");
+    let mut scored: Vec<(i32, String)> = functions
+        .iter()
+        .filter_map(|function| {
+            function
+                .matching_score(current_token)
+                .map(|score| (score, function.to_complete_string()))
+        })
+        .collect();
+    scored.sort_by(|(left_score, left), (right_score, right)| {
+        right_score.cmp(left_score).then_with(|| left.cmp(right))
+    });
+    scored.into_iter().map(|(_, completion)| completion).collect()
+}
+
+/// Signature and documentation of the function a completion candidate inserts.
+pub fn get_completion_details(candidate: &str) -> Option<(String, String)> {
+    let name = candidate.trim_end_matches(')').trim_end_matches('(');
+    let functions = FUNCTIONS.lock().expect("Failed to resolve functions");
+    functions
+        .iter()
+        .find(|function| function.name == name)
+        .map(|function| (function.to_signature_string(), function.doc.clone()))
+}
+
+/// Resolves the innermost function call the cursor is in, along with the argument being typed.
+pub fn get_signature_help(editor_state: &EditorState) -> Option<SignatureHelp> {
+    let (name, active_argument) = find_enclosing_call(&editor_state.buffer[..editor_state.cursor])?;
+    let functions = FUNCTIONS.lock().expect("Failed to resolve functions");
+    let function = functions.iter().find(|function| function.name == name)?;
+    Some(SignatureHelp {
+        name,
+        arguments: function.arguments.iter().map(|argument| argument.to_signature_string()).collect(),
+        returns: function.returns.to_string(),
+        doc: function.doc.clone(),
+        active_argument,
+    })
+}
+
+fn find_enclosing_call(input: &[char]) -> Option<(String, usize)> {
+    let mut calls: Vec<(Option<String>, usize)> = vec![];
+    let mut in_string = false;
+    for (index, character) in input.iter().enumerate() {
+        match character {
+            '"' => in_string = !in_string,
+            _ if in_string => (),
+            '(' => {
+                let label_start = get_current_token_start(&input[..index]);
+                let label = (label_start < index).then(|| input[label_start..index].iter().collect());
+                calls.push((label, 0));
+            }
+            ')' => {
+                calls.pop();
+            }
+            ',' => {
+                if let Some((_, argument_index)) = calls.last_mut() {
+                    *argument_index += 1;
+                }
+            }
+            _ => (),
+        }
+    }
+    match calls.pop() {
+        Some((Some(name), argument_index)) => Some((name, argument_index)),
+        _ => None,
+    }
+}
+
+/// Candidates shown under the prompt along with the highlighted one.
+pub fn get_visible_completions(editor_state: &EditorState) -> (Vec<String>, usize) {
+    if let Some(completion) = &editor_state.completion {
+        return (completion.candidates.clone(), completion.index);
+    }
+    match get_current_token(&editor_state.buffer[..editor_state.cursor]) {
+        Some(current_token) => (get_completions(&current_token), 0),
+        None => (vec![], 0),
+    }
+}
+
+/// Inserts the selected candidate in the buffer, or cycles to the next one when a completion
+/// has already been inserted.
+pub fn complete(editor_state: &mut EditorState) {
+    if editor_state.completion.is_some() {
+        cycle_completion(editor_state, 1);
+        return;
+    }
+    let (candidates, index) = get_visible_completions(editor_state);
+    if candidates.is_empty() {
+        return;
+    }
+    let token_start = get_current_token_start(&editor_state.buffer[..editor_state.cursor]);
+    replace_completed_text(editor_state, token_start, &candidates[index]);
+    editor_state.completion = Some(Completion {
+        candidates,
+        index,
+        token_start,
+    });
+}
+
+/// Moves the highlighted candidate of the completion in progress, replacing the inserted text.
+pub fn cycle_completion(editor_state: &mut EditorState, step: isize) {
+    if let Some(completion) = editor_state.completion.as_mut() {
+        let count = completion.candidates.len() as isize;
+        completion.index = (completion.index as isize + step).rem_euclid(count) as usize;
+        let (token_start, candidate) = (completion.token_start, completion.candidates[completion.index].clone());
+        replace_completed_text(editor_state, token_start, &candidate);
+    }
+}
+
+/// Replaces the text between the token start and the cursor by the candidate.
+fn replace_completed_text(editor_state: &mut EditorState, token_start: usize, candidate: &str) {
+    let cursor = editor_state.cursor;
+    editor_state.buffer.splice(token_start..cursor, candidate.chars());
+    editor_state.cursor = token_start + candidate.chars().count();
+}
+
+pub fn reset_completion(editor_state: &mut EditorState) {
+    editor_state.completion = None;
+}
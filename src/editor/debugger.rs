@@ -0,0 +1,152 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use super::{interpreter::CALL_STACK, limits::is_cancel_requested};
+
+/// Name shown in the call stack for the expressions typed in the prompt.
+pub const TOP_LEVEL_FUNCTION: &str = "<prompt>";
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+pub static DEBUGGER_STATE: Mutex<DebuggerState> = Mutex::new(DebuggerState {
+    enabled: false,
+    breakpoints: vec![],
+    paused: None,
+    command: None,
+    step_depth: None,
+    source: None,
+});
+
+pub struct DebuggerState {
+    /// Pauses before the first statement of every run when enabled.
+    pub enabled: bool,
+    pub breakpoints: Vec<Breakpoint>,
+    pub paused: Option<DebugLocation>,
+    command: Option<DebugCommand>,
+    /// Pauses at the next statement whose call depth is lower or equal to this one.
+    step_depth: Option<usize>,
+    /// Lines of the prompt being run, line breakpoints refer to them.
+    source: Option<Arc<[String]>>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Breakpoint {
+    Function(String),
+    /// Line of the prompt being run, statements of functions defined in other prompts never match.
+    Line(u32),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum DebugCommand {
+    StepInto,
+    StepOver,
+    Continue,
+}
+
+pub struct DebugLocation {
+    pub function: String,
+    pub line: u32,
+    pub statement: String,
+}
+
+/// Prepares the debugger for a new run of the given source. Returns the lines of the source, that
+/// statements of the prompt and the functions it defines are resolved against.
+pub fn start_debug_run(source: &str) -> Arc<[String]> {
+    let mut debugger_state = DEBUGGER_STATE.lock().expect("Failed to get debugger state");
+    let source_lines: Arc<[String]> = source.lines().map(|line| line.to_string()).collect();
+    debugger_state.source = Some(source_lines.clone());
+    debugger_state.step_depth = debugger_state.enabled.then_some(usize::MAX);
+    debugger_state.command = None;
+    source_lines
+}
+
+/// Lines of the prompt being run, kept by the functions it defines.
+pub fn current_source() -> Arc<[String]> {
+    let debugger_state = DEBUGGER_STATE.lock().expect("Failed to get debugger state");
+    debugger_state.source.clone().unwrap_or_default()
+}
+
+pub fn end_debug_run() {
+    let mut debugger_state = DEBUGGER_STATE.lock().expect("Failed to get debugger state");
+    debugger_state.paused = None;
+    debugger_state.step_depth = None;
+}
+
+/// Called by the interpreter before each statement, with the lines of the prompt the statement
+/// comes from. Blocks the script thread while the debugger is paused, until a debug command is
+/// sent from the editor or the run is cancelled.
+pub fn debug_statement(function: &str, line: u32, statement_index: usize, source: &Arc<[String]>) {
+    let depth = CALL_STACK.lock().expect("Could not resolve CALL_STACK").len();
+    {
+        let mut debugger_state = DEBUGGER_STATE.lock().expect("Failed to get debugger state");
+        if !debugger_state.should_pause(function, line, statement_index, depth, source) {
+            return;
+        }
+        let statement = source.get(line as usize).cloned().unwrap_or_default();
+        debugger_state.paused = Some(DebugLocation { function: function.to_string(), line, statement });
+        debugger_state.command = None;
+    }
+    loop {
+        thread::sleep(PAUSE_POLL_INTERVAL);
+        let mut debugger_state = DEBUGGER_STATE.lock().expect("Failed to get debugger state");
+        if is_cancel_requested() {
+            debugger_state.paused = None;
+            debugger_state.step_depth = None;
+            return;
+        }
+        if let Some(command) = debugger_state.command.take() {
+            debugger_state.step_depth = match command {
+                DebugCommand::StepInto => Some(usize::MAX),
+                DebugCommand::StepOver => Some(depth),
+                DebugCommand::Continue => None,
+            };
+            debugger_state.paused = None;
+            return;
+        }
+    }
+}
+
+pub fn send_debug_command(command: DebugCommand) {
+    let mut debugger_state = DEBUGGER_STATE.lock().expect("Failed to get debugger state");
+    if debugger_state.paused.is_some() {
+        debugger_state.command = Some(command);
+    }
+}
+
+pub fn set_debug_mode(enabled: bool) {
+    DEBUGGER_STATE.lock().expect("Failed to get debugger state").enabled = enabled;
+}
+
+/// Adds the breakpoint, or removes it when already set. Returns whether it has been added.
+pub fn toggle_breakpoint(breakpoint: Breakpoint) -> bool {
+    let mut debugger_state = DEBUGGER_STATE.lock().expect("Failed to get debugger state");
+    match debugger_state.breakpoints.iter().position(|existing| *existing == breakpoint) {
+        Some(index) => {
+            debugger_state.breakpoints.remove(index);
+            false
+        }
+        None => {
+            debugger_state.breakpoints.push(breakpoint);
+            true
+        }
+    }
+}
+
+pub fn clear_breakpoints() {
+    DEBUGGER_STATE.lock().expect("Failed to get debugger state").breakpoints.clear();
+}
+
+impl DebuggerState {
+    fn should_pause(&self, function: &str, line: u32, statement_index: usize, depth: usize, source: &Arc<[String]>) -> bool {
+        if self.step_depth.is_some_and(|step_depth| depth <= step_depth) {
+            return true;
+        }
+        let in_current_source = self.source.as_ref().is_some_and(|current| Arc::ptr_eq(current, source));
+        self.breakpoints.iter().any(|breakpoint| match breakpoint {
+            Breakpoint::Function(name) => name == function && statement_index == 0,
+            Breakpoint::Line(breakpoint_line) => in_current_source && *breakpoint_line == line,
+        })
+    }
+}
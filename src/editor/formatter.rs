@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+
+use super::{
+    grammar::{resolve_ast, AstParseError, Expression, Function, FunctionDefinition, Operation, Operator, Primary, Unary},
+    tokenizer::{get_prompt_tokens, Token, TokenType, TokenizerError},
+};
+
+const INDENTATION: &str = "    ";
+
+#[derive(Debug)]
+pub enum FormatError {
+    TokenizerError(TokenizerError),
+    AstParseError(AstParseError),
+}
+
+/// Pretty-prints a script, one statement per line. Comments are kept where they were relative
+/// to statements, and at most one blank line is kept between statements.
+pub fn format_script(source: &str) -> Result<String, FormatError> {
+    let tokens = get_prompt_tokens(source.to_string()).map_err(FormatError::TokenizerError)?;
+    let comments: VecDeque<Token> = tokens
+        .iter()
+        .filter(|token| token.token_type == TokenType::COMMENT)
+        .cloned()
+        .collect();
+    let ast = resolve_ast(tokens).map_err(FormatError::AstParseError)?;
+    let mut formatter = Formatter { source, comments, output: String::new() };
+    formatter.format_block(&ast.tree, &ast.starts, 0, source.len());
+    Ok(formatter.output)
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    comments: VecDeque<Token>,
+    output: String,
+}
+
+impl<'a> Formatter<'a> {
+    /// Formats the statements of a block, along with the comments found before its end.
+    fn format_block(&mut self, expressions: &[Expression], starts: &[usize], depth: usize, end: usize) {
+        let mut previous_end_line = None;
+        for (index, expression) in expressions.iter().enumerate() {
+            if matches!(expression, Expression::Function(Function::Operation(Operation::Unary(Unary::Primary(Primary::Eof))))) {
+                continue;
+            }
+            let start = starts.get(index).copied().unwrap_or_default();
+            let next_start = starts.get(index + 1).copied().unwrap_or(end);
+            self.format_comments_before(start, depth, &mut previous_end_line);
+            self.separate(self.line_of(start), previous_end_line);
+            self.indent(depth);
+            let end_line = self.format_statement(expression, start, depth);
+            previous_end_line = Some(self.format_trailing_comments(end_line, next_start));
+            self.output.push('\n');
+        }
+        self.format_comments_before(end, depth, &mut previous_end_line);
+    }
+
+    /// Returns the source line the statement ends on.
+    fn format_statement(&mut self, expression: &Expression, start: usize, depth: usize) -> usize {
+        match expression {
+            Expression::Definition(definition) => self.format_definition(definition, depth),
+            Expression::Function(function) => {
+                self.output.push_str(&format_function(function));
+                self.line_of(start)
+            }
+        }
+    }
+
+    fn format_definition(&mut self, definition: &FunctionDefinition, depth: usize) -> usize {
+        self.output.push_str(&format!("fun {}({}) {{", definition.name, definition.parameters.join(", ")));
+        let has_inner_comments = self.comments.front().is_some_and(|comment| comment.start < definition.end);
+        if definition.body.is_empty() && !has_inner_comments {
+            self.output.push('}');
+        } else {
+            self.output.push('\n');
+            self.format_block(&definition.body, &definition.starts, depth + 1, definition.end);
+            self.indent(depth);
+            self.output.push('}');
+        }
+        self.line_of(definition.end)
+    }
+
+    /// Comments placed before the offset, each on its own line.
+    fn format_comments_before(&mut self, offset: usize, depth: usize, previous_end_line: &mut Option<usize>) {
+        while let Some(comment) = self.comments.front().filter(|comment| comment.start < offset).cloned() {
+            self.comments.pop_front();
+            self.separate(self.line_of(comment.start), *previous_end_line);
+            self.indent(depth);
+            self.output.push_str(self.comment_text(&comment));
+            self.output.push('\n');
+            *previous_end_line = Some(self.line_of(comment.end));
+        }
+    }
+
+    /// Comments on the line a statement ends on, appended to that line. Returns the line the
+    /// last of them ends on.
+    fn format_trailing_comments(&mut self, end_line: usize, next_start: usize) -> usize {
+        let mut last_line = end_line;
+        while let Some(comment) = self
+            .comments
+            .front()
+            .filter(|comment| comment.start < next_start && self.line_of(comment.start) == end_line)
+            .cloned()
+        {
+            self.comments.pop_front();
+            self.output.push(' ');
+            self.output.push_str(self.comment_text(&comment));
+            last_line = self.line_of(comment.end);
+        }
+        last_line
+    }
+
+    /// Keeps one blank line when the original source had some between two items.
+    fn separate(&mut self, line: usize, previous_end_line: Option<usize>) {
+        if previous_end_line.is_some_and(|previous_end_line| line > previous_end_line + 1) {
+            self.output.push('\n');
+        }
+    }
+
+    fn indent(&mut self, depth: usize) {
+        self.output.push_str(&INDENTATION.repeat(depth));
+    }
+
+    fn comment_text(&self, comment: &Token) -> &'a str {
+        self.source[comment.start..comment.end].trim_end()
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.source[..offset.min(self.source.len())].matches('\n').count()
+    }
+}
+
+fn format_function(function: &Function) -> String {
+    match function {
+        Function::NamedGroup(arguments, name) => format!("{}({})", name, format_arguments(arguments)),
+        Function::Group(arguments) => format!("({})", format_arguments(arguments)),
+        Function::Operation(operation) => format_operation(operation),
+    }
+}
+
+fn format_arguments(arguments: &[Expression]) -> String {
+    let arguments: Vec<String> = arguments.iter().map(format_inline_expression).collect();
+    arguments.join(", ")
+}
+
+/// Formats an expression on a single line, as needed inside groups.
+fn format_inline_expression(expression: &Expression) -> String {
+    match expression {
+        Expression::Function(function) => format_function(function),
+        Expression::Definition(definition) => {
+            let body: Vec<String> = definition.body.iter().map(format_inline_expression).collect();
+            format!("fun {}({}) {{ {} }}", definition.name, definition.parameters.join(", "), body.join(" "))
+        }
+    }
+}
+
+fn format_operation(operation: &Operation) -> String {
+    match operation {
+        Operation::Operation(left, operator, right) => {
+            format!("{} {} {}", format_operation(left), format_operator(operator), format_operation(right))
+        }
+        Operation::Unary(unary) => format_unary(unary),
+    }
+}
+
+fn format_unary(unary: &Unary) -> String {
+    match unary {
+        Unary::Bang(unary) => format!("!{}", format_unary(unary)),
+        Unary::Minus(unary) => format!("-{}", format_unary(unary)),
+        Unary::Primary(primary) => format_primary(primary),
+    }
+}
+
+fn format_primary(primary: &Primary) -> String {
+    match primary {
+        Primary::Number(number) => number.to_string(),
+        Primary::Str(string) => format!("\"{}\"", string),
+        Primary::True => "true".to_string(),
+        Primary::False => "false".to_string(),
+        Primary::Nil => "nil".to_string(),
+        Primary::Variable(name) => name.clone(),
+        Primary::Eof => String::new(),
+    }
+}
+
+fn format_operator(operator: &Operator) -> &'static str {
+    match operator {
+        Operator::Add => "+",
+        Operator::Minus => "-",
+        Operator::Multiply => "*",
+        Operator::Divide => "/",
+        Operator::EqualEqual => "==",
+        Operator::BangEqual => "!=",
+        Operator::Less => "<",
+        Operator::LessOrEqual => "<=",
+        Operator::Greater => ">",
+        Operator::GreaterOrEqual => ">=",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_script;
+    use crate::editor::{grammar::resolve_ast, grammar::Expression, tokenizer::get_prompt_tokens};
+
+    fn parse(source: &str) -> Vec<Expression> {
+        let tokens = get_prompt_tokens(source.to_string()).expect("Script should tokenize");
+        resolve_ast(tokens).expect("Script should parse").tree
+    }
+
+    fn assert_round_trip(source: &str) {
+        let formatted = format_script(source).expect("Script should format");
+        assert_eq!(parse(source), parse(&formatted), "formatted script was :\n{}", formatted);
+        assert_eq!(formatted, format_script(&formatted).expect("Formatted script should format"));
+    }
+
+    #[test]
+    fn round_trips_calls_and_operations() {
+        assert_round_trip("moveUp()  breakDown( )\nzoomIn()");
+        assert_round_trip("1+2*3-  -4");
+        assert_round_trip("!true == false");
+        assert_round_trip("theme( \"light\" )  themeColor(\"keyword\",255,0 , 0)");
+        assert_round_trip("1.5 <= 2   \"a\"+nil");
+    }
+
+    #[test]
+    fn round_trips_function_definitions() {
+        assert_round_trip("fun walk(steps,direction){moveUp() moveUp()} walk(1, \"up\")");
+        assert_round_trip("fun nothing() {}");
+        assert_round_trip("fun outer() { fun inner(a) { a } inner(1) }");
+    }
+
+    #[test]
+    fn round_trips_doc_comments() {
+        assert_round_trip("// Walks up twice\n// then stops\nfun walk() { moveUp() moveUp() }");
+        assert_round_trip("moveUp() /* doc */ fun walk() { moveUp() }");
+        assert_round_trip("fun a() {} // doc of b\nfun b() {}");
+        assert_round_trip("// not a doc\n\nfun a() {}");
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let source = "// header\nmoveUp() // trailing\n\n\n/* block\n   comment */\nfun walk() { // opening\n  moveUp()\n  // before end\n}\n// footer";
+        let formatted = format_script(source).expect("Script should format");
+        assert_eq!(
+            formatted,
+            "// header\nmoveUp() // trailing\n\n/* block\n   comment */\nfun walk() {\n    // opening\n    moveUp()\n    // before end\n}\n// footer\n"
+        );
+        assert_round_trip(source);
+    }
+
+    #[test]
+    fn formats_one_statement_per_line() {
+        let formatted = format_script("fun walk(a,b){moveUp() moveLeft()}walk(1,2)").expect("Script should format");
+        assert_eq!(formatted, "fun walk(a, b) {\n    moveUp()\n    moveLeft()\n}\nwalk(1, 2)\n");
+    }
+}
@@ -1,216 +1,533 @@
-use std::{fmt::Arguments, sync::Mutex};
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
 
 use lazy_static::lazy_static;
 
-use crate::{editor::grammar::{Function, Operation, Primary, Unary}, game_state::{BreakError, Direction, MoveError, MAP_STATE}};
+#[cfg(feature = "game")]
+use super::natives::*;
+use super::grammar::{Expression, Function, FunctionDefinition, Operation, Primary, Unary};
 
-use super::{grammar::Expression, interpreter::InterpreterResult};
+/// Builds without the game only describe native functions, they can't run them.
+#[cfg(not(feature = "game"))]
+pub type NativeFunction = ();
+
+#[cfg(feature = "game")]
+macro_rules! native {
+    ($function:ident) => {
+        InstructionsDef::NativeFunction($function)
+    };
+}
+
+#[cfg(not(feature = "game"))]
+macro_rules! native {
+    ($function:ident) => {
+        InstructionsDef::NativeFunction(())
+    };
+}
 
 lazy_static! {
     pub static ref FUNCTIONS: Mutex<Vec<FunctionDef>> = Mutex::new(vec![
         FunctionDef {
             name: "test".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::Expressions(vec![Expression::Function(Function::Operation(Operation::Unary(Unary::Primary(Primary::Str("Working".to_string())))))])
+            returns: ValueType::Str,
+            doc: "Returns a sample string to check that functions work.".to_string(),
+            instructions: InstructionsDef::Expressions(vec![Expression::Function(Function::Operation(Operation::Unary(Unary::Primary(Primary::Str("Working".to_string())))))], vec![0], Arc::from(["\"Working\"".to_string()]))
         },
         FunctionDef {
             name: "moveDown".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(move_down)
+            returns: ValueType::Nil,
+            doc: "Moves the player one tile down.".to_string(),
+            instructions: native!(move_down)
         },
         FunctionDef {
             name: "moveUp".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(move_up)
+            returns: ValueType::Nil,
+            doc: "Moves the player one tile up.".to_string(),
+            instructions: native!(move_up)
         },
         FunctionDef {
             name: "moveLeft".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(move_left)
+            returns: ValueType::Nil,
+            doc: "Moves the player one tile left.".to_string(),
+            instructions: native!(move_left)
         },
         FunctionDef {
             name: "moveRight".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(move_right)
+            returns: ValueType::Nil,
+            doc: "Moves the player one tile right.".to_string(),
+            instructions: native!(move_right)
+        },
+        FunctionDef {
+            name: "pathTo".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "x".to_string(), value_type: ValueType::Num, optional: false },
+                ArgumentDef { name: "y".to_string(), value_type: ValueType::Num, optional: false },
+            ],
+            returns: ValueType::Str,
+            doc: "Returns the shortest safe path to the tile as a string, since scripts have no lists. Moves are up, down, left or right, separated by commas without spaces, e.g. \"up,up,left\". The string is empty when the player is already on the tile.".to_string(),
+            instructions: native!(path_to)
+        },
+        FunctionDef {
+            name: "goTo".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "x".to_string(), value_type: ValueType::Num, optional: false },
+                ArgumentDef { name: "y".to_string(), value_type: ValueType::Num, optional: false },
+            ],
+            returns: ValueType::Num,
+            doc: "Queues the moves of the shortest safe path to the tile and returns their count.".to_string(),
+            instructions: native!(go_to)
         },
         FunctionDef {
             name: "zoomOut".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(zoom_out)
+            returns: ValueType::Nil,
+            doc: "Zooms the camera out.".to_string(),
+            instructions: native!(zoom_out)
         },
         FunctionDef {
             name: "zoomIn".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(zoom_in)
+            returns: ValueType::Nil,
+            doc: "Zooms the camera in.".to_string(),
+            instructions: native!(zoom_in)
         },
         FunctionDef {
             name: "loot".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(loot)
+            returns: ValueType::Nil,
+            doc: "Picks up the item lying on the player tile.".to_string(),
+            instructions: native!(loot)
         },
         FunctionDef {
             name: "breakDown".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(break_down)
+            returns: ValueType::Nil,
+            doc: "Starts breaking the tile below the player.".to_string(),
+            instructions: native!(break_down)
         },
         FunctionDef {
             name: "breakUp".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(break_up)
+            returns: ValueType::Nil,
+            doc: "Starts breaking the tile above the player.".to_string(),
+            instructions: native!(break_up)
         },
         FunctionDef {
             name: "breakLeft".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(break_left)
+            returns: ValueType::Nil,
+            doc: "Starts breaking the tile left of the player.".to_string(),
+            instructions: native!(break_left)
         },
         FunctionDef {
             name: "breakRight".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(break_right)
+            returns: ValueType::Nil,
+            doc: "Starts breaking the tile right of the player.".to_string(),
+            instructions: native!(break_right)
+        },
+        FunctionDef {
+            name: "attack".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "direction".to_string(), value_type: ValueType::Str, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Attacks the creature up, down, left or right of the player, harder with a better tool.".to_string(),
+            instructions: native!(attack)
+        },
+        FunctionDef {
+            name: "look".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "direction".to_string(), value_type: ValueType::Str, optional: false },
+            ],
+            returns: ValueType::Str,
+            doc: "Returns the creature or else the tile up, down, left or right of the player, e.g. Wraith or Ground.".to_string(),
+            instructions: native!(look)
+        },
+        FunctionDef {
+            name: "biome".to_string(),
+            arguments: vec![],
+            returns: ValueType::Str,
+            doc: "Returns the biome under the player : plains, forest, desert, volcanic or lake.".to_string(),
+            instructions: native!(biome)
+        },
+        FunctionDef {
+            name: "health".to_string(),
+            arguments: vec![],
+            returns: ValueType::Num,
+            doc: "Returns the health of the player, from 0 to 100.".to_string(),
+            instructions: native!(health)
+        },
+        FunctionDef {
+            name: "hunger".to_string(),
+            arguments: vec![],
+            returns: ValueType::Num,
+            doc: "Returns the hunger meter of the player, from 0 when starving to 100.".to_string(),
+            instructions: native!(hunger)
+        },
+        FunctionDef {
+            name: "thirst".to_string(),
+            arguments: vec![],
+            returns: ValueType::Num,
+            doc: "Returns the thirst meter of the player, from 0 when dehydrated to 100.".to_string(),
+            instructions: native!(thirst)
+        },
+        FunctionDef {
+            name: "eat".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "id".to_string(), value_type: ValueType::Str, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Eats a food item from the inventory to restore hunger.".to_string(),
+            instructions: native!(eat)
+        },
+        FunctionDef {
+            name: "drink".to_string(),
+            arguments: vec![],
+            returns: ValueType::Nil,
+            doc: "Drinks from water under or next to the player to restore thirst.".to_string(),
+            instructions: native!(drink)
+        },
+        FunctionDef {
+            name: "time".to_string(),
+            arguments: vec![],
+            returns: ValueType::Num,
+            doc: "Returns the hour of the day, from 0 to 24. Nights last from 20 to 5.".to_string(),
+            instructions: native!(time)
         },
         FunctionDef {
             name: "inventory".to_string(),
             arguments: vec![],
-            instructions: InstructionsDef::NativeFunction(inventory)
+            returns: ValueType::Nil,
+            doc: "Shows or hides the inventory.".to_string(),
+            instructions: native!(inventory)
+        },
+        FunctionDef {
+            name: "equip".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "id".to_string(), value_type: ValueType::Str, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Equips a tool from the inventory by item id, used to break tiles.".to_string(),
+            instructions: native!(equip)
+        },
+        FunctionDef {
+            name: "place".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "direction".to_string(), value_type: ValueType::Str, optional: false },
+                ArgumentDef { name: "id".to_string(), value_type: ValueType::Str, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Places an item from the inventory on the tile up, down, left or right of the player.".to_string(),
+            instructions: native!(place)
+        },
+        FunctionDef {
+            name: "craft".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "id".to_string(), value_type: ValueType::Str, optional: false },
+                ArgumentDef { name: "times".to_string(), value_type: ValueType::Num, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Crafts an item the given number of times, consuming the recipe inputs from the inventory.".to_string(),
+            instructions: native!(craft)
+        },
+        FunctionDef {
+            name: "canCraft".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "id".to_string(), value_type: ValueType::Str, optional: false },
+            ],
+            returns: ValueType::Bool,
+            doc: "Returns whether the inventory holds the inputs to craft the item once.".to_string(),
+            instructions: native!(can_craft)
+        },
+        FunctionDef {
+            name: "recipes".to_string(),
+            arguments: vec![],
+            returns: ValueType::Nil,
+            doc: "Prints every recipe, marking the ones the inventory can craft.".to_string(),
+            instructions: native!(recipes)
+        },
+        FunctionDef {
+            name: "theme".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "name".to_string(), value_type: ValueType::Str, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Switches the editor colour theme, either \"dark\" or \"light\".".to_string(),
+            instructions: native!(theme)
+        },
+        FunctionDef {
+            name: "themeColor".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "kind".to_string(), value_type: ValueType::Str, optional: false },
+                ArgumentDef { name: "red".to_string(), value_type: ValueType::Num, optional: false },
+                ArgumentDef { name: "green".to_string(), value_type: ValueType::Num, optional: false },
+                ArgumentDef { name: "blue".to_string(), value_type: ValueType::Num, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Changes one colour of the editor theme, for example themeColor(\"keyword\", 255, 0, 0).".to_string(),
+            instructions: native!(theme_color)
+        },
+        FunctionDef {
+            name: "help".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "name".to_string(), value_type: ValueType::Str, optional: true },
+            ],
+            returns: ValueType::Nil,
+            doc: "Prints the documentation of a function, or lists every function when called without argument.".to_string(),
+            instructions: native!(help)
+        },
+        FunctionDef {
+            name: "debug".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "enabled".to_string(), value_type: ValueType::Bool, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Pauses every following run before its first statement. Use F11 to step, F10 to step over and F5 to continue.".to_string(),
+            instructions: native!(debug)
+        },
+        FunctionDef {
+            name: "breakpoint".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "target".to_string(), value_type: ValueType::Any, optional: false },
+            ],
+            returns: ValueType::Bool,
+            doc: "Toggles a breakpoint on a function name or a line number, starting at 1. Returns true when the breakpoint is added.".to_string(),
+            instructions: native!(breakpoint)
+        },
+        FunctionDef {
+            name: "clearBreakpoints".to_string(),
+            arguments: vec![],
+            returns: ValueType::Nil,
+            doc: "Removes every breakpoint.".to_string(),
+            instructions: native!(clear_all_breakpoints)
+        },
+        FunctionDef {
+            name: "profile".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "enabled".to_string(), value_type: ValueType::Bool, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Starts recording calls, time and game actions of every function, or stops recording. Calls still running when recording starts or stops are left out.".to_string(),
+            instructions: native!(profile)
+        },
+        FunctionDef {
+            name: "profileReport".to_string(),
+            arguments: vec![],
+            returns: ValueType::Nil,
+            doc: "Prints the recorded profile as a table, sorted by inclusive time.".to_string(),
+            instructions: native!(profile_report_table)
+        },
+        FunctionDef {
+            name: "profileExport".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "path".to_string(), value_type: ValueType::Str, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Writes the recorded profile as folded stacks, readable by flamegraph tools.".to_string(),
+            instructions: native!(profile_export)
+        },
+        FunctionDef {
+            name: "limits".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "steps".to_string(), value_type: ValueType::Num, optional: false },
+                ArgumentDef { name: "depth".to_string(), value_type: ValueType::Num, optional: false },
+                ArgumentDef { name: "allocations".to_string(), value_type: ValueType::Num, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Sets the maximum steps and call depth of a run, and its allocation budget: every value created counts, even once dropped. Ctrl+C cancels a running script.".to_string(),
+            instructions: native!(limits)
+        },
+        FunctionDef {
+            name: "record".to_string(),
+            arguments: vec![
+                ArgumentDef { name: "name".to_string(), value_type: ValueType::Str, optional: false },
+            ],
+            returns: ValueType::Nil,
+            doc: "Starts recording the following successful prompts into a new function, until stop() is called.".to_string(),
+            instructions: native!(record)
+        },
+        FunctionDef {
+            name: "stop".to_string(),
+            arguments: vec![],
+            returns: ValueType::Nil,
+            doc: "Stops the recording and creates the recorded function.".to_string(),
+            instructions: native!(stop)
         },
     ]);
 }
 
-pub struct FunctionDef {
-    pub name: String,
-    pub arguments: Vec<String>,
-    pub instructions: InstructionsDef,
-}
-
-impl FunctionDef {
-    pub fn is_matching(&self, input_string: String) -> bool {
-        if self.name.starts_with(&input_string) || input_string.starts_with(&self.name) {
-            return true;
-        }
-        false
-    }
-
-    pub fn to_complete_string(&self) -> String {
-        if self.arguments.is_empty() {
-            self.name.clone() + "()"
-        } else {
-            self.name.clone() + "("
-        }
+/// Registers a function, replacing any function with the same name.
+pub fn register_function(function: FunctionDef) {
+    let mut functions = FUNCTIONS.lock().expect("Could not resolve FUNCTIONS");
+    match functions.iter_mut().find(|existing| existing.name == function.name) {
+        Some(existing) => *existing = function,
+        None => functions.push(function),
     }
 }
 
-pub enum InstructionsDef {
-    Expressions(Vec<Expression>),
-    NativeFunction(fn(&Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError>),
-}
-
-#[derive(Debug)]
-pub enum FunctionError {
-    ExpectedArgumentsCount(usize),
-    PlayerMoveError(MoveError),
-    BreakSomethingError(BreakError),
-    NothingToLoot,
+pub struct FunctionDef {
+    pub name: String,
+    pub arguments: Vec<ArgumentDef>,
+    pub returns: ValueType,
+    pub doc: String,
+    pub instructions: InstructionsDef,
 }
 
-fn move_down(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    move_player(arguments, Direction::Down)
+#[derive(Clone)]
+pub struct ArgumentDef {
+    pub name: String,
+    pub value_type: ValueType,
+    /// Whether the function can be called without this argument.
+    pub optional: bool,
 }
 
-fn move_up(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    move_player(arguments, Direction::Up)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ValueType {
+    Num,
+    Str,
+    Bool,
+    Nil,
+    Any,
 }
 
-fn move_left(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    move_player(arguments, Direction::Left)
+impl Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueType::Num => write!(f, "Number"),
+            ValueType::Str => write!(f, "String"),
+            ValueType::Bool => write!(f, "Bool"),
+            ValueType::Nil => write!(f, "Nil"),
+            ValueType::Any => write!(f, "Any"),
+        }
+    }
 }
 
-fn move_right(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    move_player(arguments, Direction::Right)
+impl ArgumentDef {
+    pub fn to_signature_string(&self) -> String {
+        let optional = if self.optional { "?" } else { "" };
+        format!("{}{}: {}", self.name, optional, self.value_type)
+    }
 }
 
-fn expected_empty_arguments(arguments: &Vec<InterpreterResult>) -> Result<(), FunctionError> {
-    if !arguments.is_empty() {
-        return Err(FunctionError::ExpectedArgumentsCount(0));
+impl FunctionDef {
+    /// Builds the user function registered by a `fun` definition, given the lines of the prompt it
+    /// has been typed in.
+    pub fn from_definition(definition: &FunctionDefinition, source: Arc<[String]>) -> FunctionDef {
+        FunctionDef {
+            name: definition.name.clone(),
+            arguments: definition
+                .parameters
+                .iter()
+                .map(|parameter| ArgumentDef { name: parameter.clone(), value_type: ValueType::Any, optional: false })
+                .collect(),
+            returns: ValueType::Any,
+            doc: definition.doc.clone(),
+            instructions: InstructionsDef::Expressions(definition.body.clone(), definition.lines.clone(), source),
+        }
     }
-    Ok(())
-}
 
-fn move_player(
-    arguments: &Vec<InterpreterResult>,
-    direction: Direction,
-) -> Result<InterpreterResult, FunctionError> {
-    expected_empty_arguments(arguments)?;
-    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
-    match map_state.may_move_player(direction) {
-        Ok(_) => Ok(InterpreterResult::Nil),
-        Err(error) => Err(FunctionError::PlayerMoveError(error)),
+    pub fn to_signature_string(&self) -> String {
+        let arguments: Vec<String> = self.arguments.iter().map(|argument| argument.to_signature_string()).collect();
+        format!("{}({}) -> {}", self.name, arguments.join(", "), self.returns)
     }
-}
 
-fn break_down(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    break_something(arguments, Direction::Down)
-}
+    /// Fuzzy matches the input as a case insensitive subsequence of the function name.
+    /// Prefixes, consecutive characters and camelCase word starts rank higher.
+    pub fn matching_score(&self, input_string: &str) -> Option<i32> {
+        if input_string.is_empty() {
+            return None;
+        }
+        let name: Vec<char> = self.name.chars().collect();
+        let mut score = 0;
+        let mut name_index = 0;
+        let mut previous_match: Option<usize> = None;
+        for input_character in input_string.chars() {
+            let input_character = input_character.to_ascii_lowercase();
+            while name_index < name.len() && name[name_index].to_ascii_lowercase() != input_character {
+                name_index += 1;
+            }
+            if name_index == name.len() {
+                return None;
+            }
+            score += 1;
+            if name_index == 0 {
+                score += 10;
+            } else if name[name_index].is_uppercase() {
+                score += 5;
+            }
+            if previous_match.is_some_and(|previous| previous + 1 == name_index) {
+                score += 5;
+            }
+            if name[name_index] == input_character {
+                score += 1;
+            }
+            previous_match = Some(name_index);
+            name_index += 1;
+        }
+        if self.name.starts_with(input_string) {
+            score += 20;
+        }
+        Some(score - (name.len() as i32 - input_string.chars().count() as i32))
+    }
 
-fn break_up(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    break_something(arguments, Direction::Up)
+    pub fn to_complete_string(&self) -> String {
+        if self.arguments.is_empty() {
+            self.name.clone() + "()"
+        } else {
+            self.name.clone() + "("
+        }
+    }
 }
 
-fn break_left(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    break_something(arguments, Direction::Left)
+#[derive(Clone)]
+pub enum InstructionsDef {
+    /// Expressions of a user function, along with the line of each one in the source lines of the
+    /// prompt defining the function.
+    Expressions(Vec<Expression>, Vec<u32>, Arc<[String]>),
+    NativeFunction(NativeFunction),
 }
 
-fn break_right(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    break_something(arguments, Direction::Right)
-}
 
-fn inventory(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
-    map_state.is_inventory_toggled = !map_state.is_inventory_toggled;
-    Ok(InterpreterResult::Nil)
-}
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
 
-fn break_something(
-    arguments: &Vec<InterpreterResult>,
-    direction: Direction,
-) -> Result<InterpreterResult, FunctionError> {
-    expected_empty_arguments(arguments)?;
-    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
-    match map_state.may_break_something(direction) {
-        Ok(_) => Ok(InterpreterResult::Nil),
-        Err(error) => Err(FunctionError::BreakSomethingError(error)),
-    }
-}
+    use super::{FunctionDef, InstructionsDef, ValueType};
 
-fn zoom_out(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    expected_empty_arguments(arguments)?;
-    let mut map_state = MAP_STATE.lock().expect("Failed to load map state");
-    if map_state.zoom > 0.1 {
-        map_state.zoom -= 0.1;
+    fn function(name: &str) -> FunctionDef {
+        FunctionDef {
+            name: name.to_string(),
+            arguments: vec![],
+            returns: ValueType::Nil,
+            doc: String::new(),
+            instructions: InstructionsDef::Expressions(vec![], vec![], Arc::default()),
+        }
     }
-    Ok(InterpreterResult::Nil)
-}
 
-fn zoom_in(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    expected_empty_arguments(arguments)?;
-    let mut map_state = MAP_STATE.lock().expect("Failed to load map state");
-    if map_state.zoom < 2.0 {
-        map_state.zoom += 0.1;
+    #[test]
+    fn matches_case_insensitive_subsequences() {
+        assert!(function("moveUp").matching_score("mu").is_some());
+        assert!(function("moveUp").matching_score("MOVEUP").is_some());
+        assert!(function("breakDown").matching_score("bkdn").is_some());
+        assert_eq!(function("moveUp").matching_score("um"), None);
+        assert_eq!(function("moveUp").matching_score("moveUpx"), None);
+        assert_eq!(function("moveUp").matching_score(""), None);
     }
-    Ok(InterpreterResult::Nil)
-}
 
-fn loot(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
-    expected_empty_arguments(arguments)?;
-    let mut map_state = MAP_STATE.lock().expect("Failed to load map state");
-    let player_position = map_state.player.position;
-    for (index, item) in map_state.items.iter_mut().enumerate() {
-        if item.position.x == player_position.x && item.position.y == player_position.y {
-            let inventory_item = map_state.items[index].item.to_inventory_item();
-            map_state.player.add_item_in_inventory(inventory_item);
-            map_state.items.remove(index);
-            return Ok(InterpreterResult::Nil)
-        }
+    #[test]
+    fn ranks_prefixes_and_word_starts_higher() {
+        let score = |name: &str, input: &str| function(name).matching_score(input).expect("Input should match");
+        assert!(score("moveUp", "move") > score("removeAll", "move"));
+        assert!(score("moveUp", "mu") > score("mouser", "mu"));
+        assert!(score("zoomIn", "zoomI") > score("zoomOut", "zo"));
+        assert!(score("loot", "lo") > score("lootAll", "lo"));
     }
-    Err(FunctionError::NothingToLoot)
 }
-
@@ -1,4 +1,4 @@
-use std::slice::Iter;
+use std::{ops::Range, slice::Iter};
 
 use super::tokenizer::{Literal, Token, TokenType};
 
@@ -12,49 +12,85 @@ pub enum AstParseError {
     InvalidFactorExpressions,
     LabelWithNoValidNextToken,
     InvalidTokensInGroup,
+    InvalidFunctionDefinition,
+    UnclosedBlock,
 }
 
 #[derive(Debug)]
 pub struct Ast {
     pub tree: Vec<Expression>,
+    /// Line of each expression of the tree, used by the debugger.
+    pub lines: Vec<u32>,
+    /// Byte offset where each expression of the tree starts, used by the formatter.
+    pub starts: Vec<usize>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Expression {
     Function(Function),
+    Definition(FunctionDefinition),
 }
 
 #[derive(Clone, Debug)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub body: Vec<Expression>,
+    pub lines: Vec<u32>,
+    pub starts: Vec<usize>,
+    /// Byte offset of the closing brace.
+    pub end: usize,
+    pub doc: String,
+}
+
+/// Source positions are not part of the tree, two definitions only differing by them are equal.
+impl PartialEq for FunctionDefinition {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.parameters == other.parameters && self.body == other.body && self.doc == other.doc
+    }
+}
+
+/// Expressions resolved up to a closing token, with the line and byte offset each one starts at.
+struct Block {
+    expressions: Vec<Expression>,
+    lines: Vec<u32>,
+    starts: Vec<usize>,
+    /// Byte offset of the closing token, or of the end of tokens when there is none.
+    end: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Function {
     NamedGroup(Vec<Expression>, String),
     Group(Vec<Expression>),
     Operation(Operation),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Operation {
     Operation(Box<Operation>, Operator, Box<Operation>),
     Unary(Unary),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Unary {
     Bang(Box<Unary>),
     Minus(Box<Unary>),
     Primary(Primary),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Primary {
     Number(f64),
     Str(String),
     True,
     False,
     Nil,
+    Variable(String),
     Eof,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Operator {
     Add,
     Minus,
@@ -69,32 +105,76 @@ pub enum Operator {
 }
 
 pub fn resolve_ast(tokens: Vec<Token>) -> Result<Ast, AstParseError> {
-    let mut previous_expression: Option<Expression> = None;
-    let mut ast = Ast { tree: vec![] };
+    resolve_located_ast(tokens).map_err(|(_, error)| error)
+}
 
+/// Resolves the tree like `resolve_ast`, returning the byte range of the token the grammar
+/// failed on along with the error.
+pub fn resolve_located_ast(tokens: Vec<Token>) -> Result<Ast, (Range<usize>, AstParseError)> {
+    let (comments, tokens): (Vec<Token>, Vec<Token>) = tokens
+        .into_iter()
+        .partition(|token| token.token_type == TokenType::COMMENT);
     let mut tokens_iter = tokens.iter();
-    while let Some(token) = tokens_iter.next() {
-        match token_to_expression(token, &mut tokens_iter, &previous_expression) {
+    match resolve_expressions(&mut tokens_iter, &comments, None) {
+        Ok(block) => Ok(Ast { tree: block.expressions, lines: block.lines, starts: block.starts }),
+        Err(error) => {
+            // Tokens are consumed up to the one the grammar failed on
+            let consumed = tokens.len() - tokens_iter.as_slice().len();
+            let range = tokens
+                .get(consumed.saturating_sub(1))
+                .map_or(0..0, |token| token.start..token.end);
+            Err((range, error))
+        }
+    }
+}
+
+/// Resolves expressions until the closing token, or until the end of tokens when there is none.
+fn resolve_expressions(
+    tokens: &mut Iter<Token>,
+    comments: &[Token],
+    closing: Option<TokenType>,
+) -> Result<Block, AstParseError> {
+    let mut previous_expression: Option<Expression> = None;
+    let mut expressions = vec![];
+    let mut lines = vec![];
+    let mut starts = vec![];
+    let mut end = 0;
+
+    while let Some(token) = tokens.next() {
+        end = token.end;
+        if Some(token.token_type) == closing {
+            return Ok(Block { expressions, lines, starts, end: token.start });
+        }
+        match token_to_expression(token, tokens, &previous_expression, comments) {
             Ok(expression) => {
+                let (mut line, mut start) = (token.line, token.start);
                 if let Expression::Function(Function::Operation(Operation::Operation(_, _, _))) =
                     expression.clone()
                 {
-                    ast.tree.pop();
+                    expressions.pop();
+                    line = lines.pop().unwrap_or(line);
+                    start = starts.pop().unwrap_or(start);
                 }
-                ast.tree.push(expression.clone());
+                expressions.push(expression.clone());
+                lines.push(line);
+                starts.push(start);
                 previous_expression = Some(expression);
             }
             Err(error) => return Err(error),
         }
     }
 
-    Ok(ast)
+    match closing {
+        Some(_) => Err(AstParseError::UnclosedBlock),
+        None => Ok(Block { expressions, lines, starts, end }),
+    }
 }
 
 fn token_to_expression(
     token: &Token,
     tokens: &mut Iter<Token>,
     previous_expression: &Option<Expression>,
+    comments: &[Token],
 ) -> Result<Expression, AstParseError> {
     // OPERATIONS
     if is_token_operator(token) {
@@ -102,7 +182,7 @@ fn token_to_expression(
             let mut tokens_peek = tokens.clone().peekable();
             if let Some(right) = tokens_peek.peek() {
                 tokens.next();
-                match token_to_expression(right, tokens, &None) {
+                match token_to_expression(right, tokens, &None, comments) {
                     Ok(right_expression) => {
                         if let Expression::Function(Function::Operation(factor_left)) =
                             previous_expression
@@ -130,8 +210,20 @@ fn token_to_expression(
     match token.token_type {
         // LABELS
         TokenType::LABEL => {
+            let is_call = tokens
+                .clone()
+                .next()
+                .is_some_and(|next_token| next_token.token_type == TokenType::LEFT_PAREN);
+            if !is_call {
+                return match token.literal.clone() {
+                    Some(Literal::Label(label)) => Ok(Expression::Function(Function::Operation(
+                        Operation::Unary(Unary::Primary(Primary::Variable(label))),
+                    ))),
+                    _ => Err(AstParseError::LabelWithNoValidNextToken),
+                };
+            }
             if let Some(next_token) = tokens.next() {
-                match token_to_expression(next_token, tokens, &None) {
+                match token_to_expression(next_token, tokens, &None, comments) {
                     Ok(expression) => match expression {
                         Expression::Function(Function::Group(group)) => {
                             let label = match token.literal.clone().expect("Label should be present") {
@@ -156,7 +248,7 @@ fn token_to_expression(
                 if next_token.token_type == TokenType::RIGHT_PAREN {
                     return Ok(Expression::Function(Function::Group(expressions)))
                 }
-                match token_to_expression(next_token, tokens, &None) {
+                match token_to_expression(next_token, tokens, &None, comments) {
                     Ok(expression) => {
                         expressions.push(expression);
                         if let Some(next_next_token) = tokens.next() {
@@ -176,7 +268,7 @@ fn token_to_expression(
         // UNARY
         TokenType::BANG => {
             if let Some(next_token) = tokens.next() {
-                match token_to_expression(next_token, tokens, &None) {
+                match token_to_expression(next_token, tokens, &None, comments) {
                     Ok(expression) => match expression {
                         Expression::Function(Function::Operation(Operation::Unary(unary))) => {
                             Ok(Expression::Function(Function::Operation(Operation::Unary(
@@ -193,7 +285,7 @@ fn token_to_expression(
         }
         TokenType::MINUS => {
             if let Some(next_token) = tokens.next() {
-                match token_to_expression(next_token, tokens, &None) {
+                match token_to_expression(next_token, tokens, &None, comments) {
                     Ok(expression) => match expression {
                         Expression::Function(Function::Operation(Operation::Unary(unary))) => {
                             Ok(Expression::Function(Function::Operation(Operation::Unary(
@@ -234,6 +326,7 @@ fn token_to_expression(
             Some(Literal::Identifier(TokenType::NIL)) => Ok(Expression::Function(
                 Function::Operation(Operation::Unary(Unary::Primary(Primary::Nil))),
             )),
+            Some(Literal::Identifier(TokenType::FUN)) => resolve_function_definition(token, tokens, comments),
             None => Err(AstParseError::MissingLiteralForIdentifier),
             _ => Err(AstParseError::MissingLiteralForIdentifier),
         },
@@ -247,6 +340,68 @@ fn token_to_expression(
     }
 }
 
+/// Parses `fun name(parameter, ...) { expressions }`. Comments right above the definition,
+/// or before it on the same line, become its documentation.
+fn resolve_function_definition(
+    fun_token: &Token,
+    tokens: &mut Iter<Token>,
+    comments: &[Token],
+) -> Result<Expression, AstParseError> {
+    let name = match tokens.next() {
+        Some(Token { token_type: TokenType::LABEL, literal: Some(Literal::Label(name)), .. }) => name.clone(),
+        _ => return Err(AstParseError::InvalidFunctionDefinition),
+    };
+    if tokens.next().map(|token| token.token_type) != Some(TokenType::LEFT_PAREN) {
+        return Err(AstParseError::InvalidFunctionDefinition);
+    }
+    let mut parameters = vec![];
+    loop {
+        match tokens.next() {
+            Some(Token { token_type: TokenType::RIGHT_PAREN, .. }) if parameters.is_empty() => break,
+            Some(Token { token_type: TokenType::LABEL, literal: Some(Literal::Label(parameter)), .. }) => {
+                parameters.push(parameter.clone());
+                match tokens.next().map(|token| token.token_type) {
+                    Some(TokenType::COMMA) => continue,
+                    Some(TokenType::RIGHT_PAREN) => break,
+                    _ => return Err(AstParseError::InvalidFunctionDefinition),
+                }
+            }
+            _ => return Err(AstParseError::InvalidFunctionDefinition),
+        }
+    }
+    if tokens.next().map(|token| token.token_type) != Some(TokenType::LEFT_BRACE) {
+        return Err(AstParseError::InvalidFunctionDefinition);
+    }
+    let block = resolve_expressions(tokens, comments, Some(TokenType::RIGHT_BRACE))?;
+    Ok(Expression::Definition(FunctionDefinition {
+        name,
+        parameters,
+        body: block.expressions,
+        lines: block.lines,
+        starts: block.starts,
+        end: block.end,
+        doc: resolve_doc_comment(fun_token, comments),
+    }))
+}
+
+fn resolve_doc_comment(fun_token: &Token, comments: &[Token]) -> String {
+    let mut doc_lines = vec![];
+    let mut expected_line = fun_token.line;
+    for comment in comments.iter().rev().filter(|comment| comment.end <= fun_token.start) {
+        let Some(Literal::Comment(text)) = &comment.literal else {
+            continue;
+        };
+        let end_line = comment.line + text.matches('\n').count() as u32;
+        if end_line != expected_line && end_line + 1 != expected_line {
+            break;
+        }
+        doc_lines.push(text.trim().to_string());
+        expected_line = comment.line;
+    }
+    doc_lines.reverse();
+    doc_lines.join(" ")
+}
+
 fn to_operator(token2: &Token) -> Operator {
     match token2.token_type {
         TokenType::PLUS => Operator::Add,
@@ -275,3 +430,75 @@ fn is_token_operator(token: &Token) -> bool {
         || token.token_type == TokenType::GREATER
         || token.token_type == TokenType::GREATER_EQUAL
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_ast, resolve_located_ast, AstParseError, Expression, Function, FunctionDefinition, Operation, Primary, Unary};
+    use crate::editor::tokenizer::get_prompt_tokens;
+
+    fn parse(source: &str) -> Result<Vec<Expression>, AstParseError> {
+        let tokens = get_prompt_tokens(source.to_string()).expect("Script should tokenize");
+        resolve_ast(tokens).map(|ast| ast.tree)
+    }
+
+    fn definition(source: &str) -> FunctionDefinition {
+        match parse(source).expect("Script should parse").into_iter().next() {
+            Some(Expression::Definition(definition)) => definition,
+            expression => panic!("Expected a function definition, got {:?}", expression),
+        }
+    }
+
+    fn variable(name: &str) -> Expression {
+        Expression::Function(Function::Operation(Operation::Unary(Unary::Primary(Primary::Variable(name.to_string())))))
+    }
+
+    #[test]
+    fn parses_function_definitions() {
+        let walk = definition("fun walk(steps, direction) {\n  moveUp()\n  steps\n}");
+        assert_eq!(walk.name, "walk");
+        assert_eq!(walk.parameters, vec!["steps", "direction"]);
+        assert_eq!(walk.body.len(), 2);
+        assert_eq!(walk.body[1], variable("steps"));
+        assert_eq!(walk.lines, vec![1, 2]);
+        assert!(definition("fun nothing() {}").body.is_empty());
+    }
+
+    #[test]
+    fn parses_labels_without_group_as_variables() {
+        assert_eq!(parse("steps").expect("Script should parse")[0], variable("steps"));
+        assert!(matches!(
+            parse("walk(steps)").expect("Script should parse")[0],
+            Expression::Function(Function::NamedGroup(_, _))
+        ));
+    }
+
+    #[test]
+    fn keeps_comments_above_a_definition_as_its_doc() {
+        assert_eq!(definition("// Walks up\n// twice\nfun walk() {}").doc, "Walks up twice");
+        assert_eq!(definition("/* Walks up */ fun walk() {}").doc, "Walks up");
+        assert_eq!(definition("// Detached\n\nfun walk() {}").doc, "");
+    }
+
+    #[test]
+    fn locates_the_token_the_grammar_failed_on() {
+        let locate = |source: &str| {
+            let tokens = get_prompt_tokens(source.to_string()).expect("Script should tokenize");
+            match resolve_located_ast(tokens) {
+                Err((range, _)) => source[range].to_string(),
+                Ok(_) => panic!("Script should not parse"),
+            }
+        };
+        assert_eq!(locate("moveUp()\nfun (a) {}"), "(");
+        assert_eq!(locate("loot() moveUp(1 2)"), "2");
+        assert_eq!(locate("fun walk() { moveUp()"), "");
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        assert!(matches!(parse("fun (a) {}"), Err(AstParseError::InvalidFunctionDefinition)));
+        assert!(matches!(parse("fun walk(a,) {}"), Err(AstParseError::InvalidFunctionDefinition)));
+        assert!(matches!(parse("fun walk(1) {}"), Err(AstParseError::InvalidFunctionDefinition)));
+        assert!(matches!(parse("fun walk() moveUp()"), Err(AstParseError::InvalidFunctionDefinition)));
+        assert!(matches!(parse("fun walk() { moveUp()"), Err(AstParseError::UnclosedBlock)));
+    }
+}
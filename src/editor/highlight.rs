@@ -0,0 +1,234 @@
+use std::{ops::Range, sync::Mutex};
+
+use raylib::color::Color;
+
+use super::{
+    functions::FUNCTIONS,
+    tokenizer::{scan_prompt_tokens, Literal, TokenType},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HighlightKind {
+    Plain,
+    Keyword,
+    Number,
+    Str,
+    Function,
+    Operator,
+    Comment,
+    Invalid,
+}
+
+pub struct Theme {
+    pub name: &'static str,
+    pub background: Color,
+    pub plain: Color,
+    pub keyword: Color,
+    pub number: Color,
+    pub string: Color,
+    pub function: Color,
+    pub operator: Color,
+    pub comment: Color,
+    pub invalid: Color,
+    pub result: Color,
+    pub error: Color,
+}
+
+impl Theme {
+    pub fn color(&self, kind: HighlightKind) -> Color {
+        match kind {
+            HighlightKind::Plain => self.plain,
+            HighlightKind::Keyword => self.keyword,
+            HighlightKind::Number => self.number,
+            HighlightKind::Str => self.string,
+            HighlightKind::Function => self.function,
+            HighlightKind::Operator => self.operator,
+            HighlightKind::Comment => self.comment,
+            HighlightKind::Invalid => self.invalid,
+        }
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        match name {
+            "background" => Some(&mut self.background),
+            "plain" => Some(&mut self.plain),
+            "keyword" => Some(&mut self.keyword),
+            "number" => Some(&mut self.number),
+            "string" => Some(&mut self.string),
+            "function" => Some(&mut self.function),
+            "operator" => Some(&mut self.operator),
+            "comment" => Some(&mut self.comment),
+            "invalid" => Some(&mut self.invalid),
+            "result" => Some(&mut self.result),
+            "error" => Some(&mut self.error),
+            _ => None,
+        }
+    }
+}
+
+pub const DARK_THEME: Theme = Theme {
+    name: "dark",
+    background: Color::BLACK,
+    plain: Color::WHITE,
+    keyword: Color::new(198, 120, 221, 255),
+    number: Color::new(209, 154, 102, 255),
+    string: Color::new(152, 195, 121, 255),
+    function: Color::new(97, 175, 239, 255),
+    operator: Color::new(86, 182, 194, 255),
+    comment: Color::new(92, 99, 112, 255),
+    invalid: Color::new(224, 108, 117, 255),
+    result: Color::GREEN,
+    error: Color::RED,
+};
+
+pub const LIGHT_THEME: Theme = Theme {
+    name: "light",
+    background: Color::new(250, 250, 250, 255),
+    plain: Color::new(56, 58, 66, 255),
+    keyword: Color::new(166, 38, 164, 255),
+    number: Color::new(152, 104, 1, 255),
+    string: Color::new(80, 161, 79, 255),
+    function: Color::new(64, 120, 242, 255),
+    operator: Color::new(1, 132, 188, 255),
+    comment: Color::new(160, 161, 167, 255),
+    invalid: Color::new(228, 86, 73, 255),
+    result: Color::new(80, 161, 79, 255),
+    error: Color::new(228, 86, 73, 255),
+};
+
+pub const THEMES: [Theme; 2] = [DARK_THEME, LIGHT_THEME];
+
+pub static EDITOR_THEME: Mutex<Theme> = Mutex::new(DARK_THEME);
+
+#[derive(Debug)]
+pub enum ThemeError {
+    UnknownTheme,
+    UnknownThemeColor,
+}
+
+pub fn set_theme(name: &str) -> Result<(), ThemeError> {
+    let theme = THEMES
+        .into_iter()
+        .find(|theme| theme.name == name)
+        .ok_or(ThemeError::UnknownTheme)?;
+    *EDITOR_THEME.lock().expect("Failed to get editor theme") = theme;
+    Ok(())
+}
+
+pub fn set_theme_color(name: &str, color: Color) -> Result<(), ThemeError> {
+    let mut theme = EDITOR_THEME.lock().expect("Failed to get editor theme");
+    let theme_color = theme.color_mut(name).ok_or(ThemeError::UnknownThemeColor)?;
+    *theme_color = color;
+    Ok(())
+}
+
+/// Splits a line in contiguous byte ranges covering the whole line, each with its highlight kind.
+/// Everything from the first character the tokenizer fails on is marked as invalid.
+pub fn highlight_line(line: &str) -> Vec<(Range<usize>, HighlightKind)> {
+    let (tokens, error) = scan_prompt_tokens(line);
+    let mut segments = vec![];
+    let mut position = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        if token.start > position {
+            segments.push((position..token.start, HighlightKind::Plain));
+        }
+        let is_call = tokens
+            .get(index + 1)
+            .is_some_and(|next| next.token_type == TokenType::LEFT_PAREN);
+        segments.push((token.start..token.end, token_kind(&token.token_type, &token.literal, is_call)));
+        position = token.end;
+    }
+    let invalid_start = match error {
+        Some((error_start, _)) => error_start,
+        None => line.len(),
+    };
+    if invalid_start > position {
+        segments.push((position..invalid_start, HighlightKind::Plain));
+    }
+    if line.len() > invalid_start {
+        segments.push((invalid_start..line.len(), HighlightKind::Invalid));
+    }
+    segments
+}
+
+/// Labels followed by a group are function calls, other labels are variables.
+fn token_kind(token_type: &TokenType, literal: &Option<Literal>, is_call: bool) -> HighlightKind {
+    match token_type {
+        TokenType::NUMBER => HighlightKind::Number,
+        TokenType::STRING => HighlightKind::Str,
+        TokenType::IDENTIFIER => HighlightKind::Keyword,
+        TokenType::COMMENT => HighlightKind::Comment,
+        TokenType::LABEL if !is_call => HighlightKind::Plain,
+        TokenType::LABEL => match literal {
+            Some(Literal::Label(label)) if is_known_function(label) => HighlightKind::Function,
+            _ => HighlightKind::Invalid,
+        },
+        TokenType::LEFT_PAREN
+        | TokenType::RIGHT_PAREN
+        | TokenType::LEFT_BRACE
+        | TokenType::RIGHT_BRACE
+        | TokenType::COMMA
+        | TokenType::DOT
+        | TokenType::SEMICOLON => HighlightKind::Plain,
+        _ => HighlightKind::Operator,
+    }
+}
+
+fn is_known_function(label: &str) -> bool {
+    let functions = FUNCTIONS.lock().expect("Failed to resolve functions");
+    functions.iter().any(|function| function.name == label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{highlight_line, HighlightKind};
+
+    fn highlighted(line: &str) -> Vec<(&str, HighlightKind)> {
+        highlight_line(line).into_iter().map(|(range, kind)| (&line[range], kind)).collect()
+    }
+
+    #[test]
+    fn highlights_each_token_kind() {
+        assert_eq!(
+            highlighted("help(\"loot\") + 2 // doc"),
+            vec![
+                ("help", HighlightKind::Function),
+                ("(", HighlightKind::Plain),
+                ("\"loot\"", HighlightKind::Str),
+                (")", HighlightKind::Plain),
+                (" ", HighlightKind::Plain),
+                ("+", HighlightKind::Operator),
+                (" ", HighlightKind::Plain),
+                ("2", HighlightKind::Number),
+                (" ", HighlightKind::Plain),
+                ("// doc", HighlightKind::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn marks_unknown_calls_and_scan_failures_invalid() {
+        assert_eq!(
+            highlighted("true unknownCall() steps"),
+            vec![
+                ("true", HighlightKind::Keyword),
+                (" ", HighlightKind::Plain),
+                ("unknownCall", HighlightKind::Invalid),
+                ("(", HighlightKind::Plain),
+                (")", HighlightKind::Plain),
+                (" ", HighlightKind::Plain),
+                ("steps", HighlightKind::Plain),
+            ]
+        );
+        assert_eq!(
+            highlighted("loot() \"open"),
+            vec![
+                ("loot", HighlightKind::Function),
+                ("(", HighlightKind::Plain),
+                (")", HighlightKind::Plain),
+                (" ", HighlightKind::Plain),
+                ("\"open", HighlightKind::Invalid),
+            ]
+        );
+    }
+}
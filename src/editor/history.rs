@@ -0,0 +1,218 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+};
+
+use crate::game_state::{push_editor_error, EditorState};
+
+use super::line_editing::set_buffer;
+
+const HISTORY_FILE_PATH: &str = ".coding_survivor_history";
+const HISTORY_MAX_LOADED_ENTRIES: usize = 1000;
+
+pub struct HistorySearch {
+    pub query: String,
+    pub match_index: Option<usize>,
+}
+
+/// Loads the history file written by previous sessions, keeping only the most recent entries.
+pub fn load_history(editor_state: &mut EditorState) {
+    let Ok(content) = fs::read_to_string(HISTORY_FILE_PATH) else {
+        return;
+    };
+    let mut entries: Vec<String> = vec![];
+    for line in content.lines() {
+        if !line.trim().is_empty() && entries.last().map(|last| last.as_str()) != Some(line) {
+            entries.push(line.to_string());
+        }
+    }
+    let skipped = entries.len().saturating_sub(HISTORY_MAX_LOADED_ENTRIES);
+    editor_state.input_history = entries.split_off(skipped);
+}
+
+/// Records a submitted prompt and appends it to the history file.
+pub fn push_history(editor_state: &mut EditorState, prompt: &str) {
+    if !add_history_entry(editor_state, prompt) {
+        return;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(HISTORY_FILE_PATH);
+    match file {
+        Ok(mut file) => {
+            if let Err(error) = writeln!(file, "{}", prompt) {
+                push_editor_error(&format!("Failed to append to history file : {}", error));
+            }
+        }
+        Err(error) => push_editor_error(&format!("Failed to open history file : {}", error)),
+    }
+}
+
+/// Adds a prompt to the history, ignoring empty prompts and consecutive duplicates.
+/// Returns whether the prompt was added.
+fn add_history_entry(editor_state: &mut EditorState, prompt: &str) -> bool {
+    editor_state.history_index = None;
+    editor_state.history_draft = vec![];
+    if prompt.trim().is_empty() || editor_state.input_history.last().map(|last| last.as_str()) == Some(prompt) {
+        return false;
+    }
+    editor_state.input_history.push(prompt.to_string());
+    true
+}
+
+pub fn history_previous(editor_state: &mut EditorState) {
+    if editor_state.input_history.is_empty() {
+        return;
+    }
+    let index = match editor_state.history_index {
+        Some(index) => index.saturating_sub(1),
+        None => {
+            editor_state.history_draft = editor_state.buffer.clone();
+            editor_state.input_history.len() - 1
+        }
+    };
+    load_history_entry(editor_state, index);
+}
+
+pub fn history_next(editor_state: &mut EditorState) {
+    let Some(index) = editor_state.history_index else {
+        return;
+    };
+    if index + 1 < editor_state.input_history.len() {
+        load_history_entry(editor_state, index + 1);
+    } else {
+        editor_state.history_index = None;
+        let draft = std::mem::take(&mut editor_state.history_draft);
+        set_buffer(editor_state, draft);
+    }
+}
+
+fn load_history_entry(editor_state: &mut EditorState, index: usize) {
+    editor_state.history_index = Some(index);
+    let entry = editor_state.input_history[index].chars().collect();
+    set_buffer(editor_state, entry);
+}
+
+/// Starts a reverse incremental search, or jumps to the next older match if one is in progress.
+pub fn reverse_search(editor_state: &mut EditorState) {
+    match editor_state.history_search.as_mut() {
+        Some(search) => {
+            let before = search.match_index.unwrap_or(editor_state.input_history.len());
+            search.match_index = find_match(&editor_state.input_history, &search.query, before)
+                .or(search.match_index);
+        }
+        None => {
+            editor_state.history_search = Some(HistorySearch {
+                query: String::new(),
+                match_index: None,
+            })
+        }
+    }
+    load_search_match(editor_state);
+}
+
+pub fn update_search_query(editor_state: &mut EditorState, character: Option<char>) {
+    if let Some(search) = editor_state.history_search.as_mut() {
+        match character {
+            Some(character) => search.query.push(character),
+            None => {
+                search.query.pop();
+            }
+        }
+        search.match_index = find_match(&editor_state.input_history, &search.query, editor_state.input_history.len());
+    }
+    load_search_match(editor_state);
+}
+
+/// Leaves the search, keeping the matched entry in the buffer.
+pub fn end_search(editor_state: &mut EditorState) {
+    editor_state.history_search = None;
+}
+
+fn load_search_match(editor_state: &mut EditorState) {
+    if let Some(search) = &editor_state.history_search {
+        let entry = match search.match_index {
+            Some(index) => editor_state.input_history[index].chars().collect(),
+            None => vec![],
+        };
+        set_buffer(editor_state, entry);
+    }
+}
+
+fn find_match(input_history: &[String], query: &str, before: usize) -> Option<usize> {
+    if query.is_empty() {
+        return None;
+    }
+    input_history[..before.min(input_history.len())]
+        .iter()
+        .rposition(|entry| entry.contains(query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_history_entry, end_search, history_next, history_previous, reverse_search, update_search_query};
+    use crate::game_state::EditorState;
+
+    fn editor_with_history(entries: &[&str]) -> EditorState {
+        let mut editor_state = EditorState::empty();
+        for entry in entries {
+            add_history_entry(&mut editor_state, entry);
+        }
+        editor_state
+    }
+
+    fn buffer(editor_state: &EditorState) -> String {
+        editor_state.buffer.iter().collect()
+    }
+
+    fn search(editor_state: &mut EditorState, query: &str) {
+        reverse_search(editor_state);
+        for character in query.chars() {
+            update_search_query(editor_state, Some(character));
+        }
+    }
+
+    #[test]
+    fn suppresses_empty_prompts_and_consecutive_duplicates() {
+        let editor_state = editor_with_history(&["moveUp()", "moveUp()", "  ", "loot()", "moveUp()", ""]);
+        assert_eq!(editor_state.input_history, vec!["moveUp()", "loot()", "moveUp()"]);
+    }
+
+    #[test]
+    fn navigates_back_and_restores_the_draft() {
+        let mut editor_state = editor_with_history(&["moveUp()", "loot()"]);
+        editor_state.buffer = "bre".chars().collect();
+        history_previous(&mut editor_state);
+        assert_eq!(buffer(&editor_state), "loot()");
+        history_previous(&mut editor_state);
+        history_previous(&mut editor_state);
+        assert_eq!(buffer(&editor_state), "moveUp()");
+        history_next(&mut editor_state);
+        assert_eq!(buffer(&editor_state), "loot()");
+        history_next(&mut editor_state);
+        assert_eq!(buffer(&editor_state), "bre");
+        assert_eq!(editor_state.history_index, None);
+    }
+
+    #[test]
+    fn reverse_search_finds_older_matches() {
+        let mut editor_state = editor_with_history(&["moveUp()", "loot()", "moveLeft()", "zoomIn()"]);
+        search(&mut editor_state, "move");
+        assert_eq!(buffer(&editor_state), "moveLeft()");
+        reverse_search(&mut editor_state);
+        assert_eq!(buffer(&editor_state), "moveUp()");
+        // No older match keeps the current one
+        reverse_search(&mut editor_state);
+        assert_eq!(buffer(&editor_state), "moveUp()");
+        end_search(&mut editor_state);
+        assert!(editor_state.history_search.is_none());
+        assert_eq!(buffer(&editor_state), "moveUp()");
+    }
+
+    #[test]
+    fn reverse_search_updates_with_the_query() {
+        let mut editor_state = editor_with_history(&["moveUp()", "loot()"]);
+        search(&mut editor_state, "moveX");
+        assert_eq!(buffer(&editor_state), "");
+        update_search_query(&mut editor_state, None);
+        assert_eq!(buffer(&editor_state), "moveUp()");
+    }
+}
@@ -1,9 +1,29 @@
+use std::{
+    fmt::Display,
+    sync::{atomic::AtomicBool, Arc, Mutex},
+};
+
 use super::{
-    functions::{FunctionError, InstructionsDef, FUNCTIONS},
-    grammar::{Expression, Function, Operation, Primary, Unary},
+    debugger::{current_source, debug_statement},
+    limits::{check_call_depth, count_allocations, count_step, LimitError},
+    functions::{register_function, FunctionDef, InstructionsDef, FUNCTIONS},
+    natives::FunctionError,
+    grammar::{Expression, Function, FunctionDefinition, Operation, Primary, Unary},
+    profiler::{enter_function, exit_function},
 };
 
-#[derive(Debug)]
+/// Frames of the user functions being executed, the last one being the current call.
+pub static CALL_STACK: Mutex<Vec<CallFrame>> = Mutex::new(vec![]);
+
+/// Whether a prompt is being executed on the script thread.
+pub static SCRIPT_RUNNING: AtomicBool = AtomicBool::new(false);
+
+pub struct CallFrame {
+    pub function: String,
+    pub variables: Vec<(String, InterpreterResult)>,
+}
+
+#[derive(Debug, Clone)]
 pub enum InterpreterResult {
     Num(f64),
     Str(String),
@@ -12,6 +32,18 @@ pub enum InterpreterResult {
     Nil,
 }
 
+impl Display for InterpreterResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpreterResult::Num(num) => write!(f, "{}", num),
+            InterpreterResult::Str(str) => write!(f, "\"{}\"", str),
+            InterpreterResult::Bool(bool) => write!(f, "{}", bool),
+            InterpreterResult::Bang(value) => write!(f, "!{}", value),
+            InterpreterResult::Nil => write!(f, "nil"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum InterpreterError {
     ExpressionNotHandled,
@@ -20,15 +52,40 @@ pub enum InterpreterError {
     UnexpectedLatelyInterpretedBang,
     InvalidNativeFunction(FunctionError),
     FunctionDoesNotExist,
+    ExpectedArgumentsCount(usize),
+    UndefinedVariable(String),
+    LimitExceeded(LimitError),
+}
+
+impl InterpreterResult {
+    /// Weight of the value against the allocation budget of the run.
+    fn size(&self) -> usize {
+        match self {
+            InterpreterResult::Str(str) => 1 + str.chars().count(),
+            InterpreterResult::Bang(value) => 1 + value.size(),
+            _ => 1,
+        }
+    }
 }
 
+/// Evaluates an expression, counting it against the limits of the current run.
 pub fn interpret_expression(
     expression: &Expression,
+) -> Result<InterpreterResult, InterpreterError> {
+    count_step().map_err(InterpreterError::LimitExceeded)?;
+    let result = solve_expression(expression)?;
+    count_allocations(result.size()).map_err(InterpreterError::LimitExceeded)?;
+    Ok(result)
+}
+
+fn solve_expression(
+    expression: &Expression,
 ) -> Result<InterpreterResult, InterpreterError> {
     match expression {
         Expression::Function(Function::NamedGroup(expressions, label)) => {
             solve_function_call(&expressions, label.to_string())
         }
+        Expression::Definition(definition) => define_function(definition),
         Expression::Function(Function::Operation(Operation::Operation(left, operator, right))) => {
             solve_operation(left, operator, right)
         }
@@ -38,6 +95,7 @@ pub fn interpret_expression(
                 Primary::Str(string) => InterpreterResult::Str(string.to_string()),
                 Primary::True => InterpreterResult::Bool(true),
                 Primary::False => InterpreterResult::Bool(false),
+                Primary::Variable(name) => return solve_variable(name),
                 _ => InterpreterResult::Nil,
             };
             Ok(result)
@@ -46,42 +104,94 @@ pub fn interpret_expression(
     }
 }
 
+/// Registers a user function, replacing any user function with the same name.
+fn define_function(definition: &FunctionDefinition) -> Result<InterpreterResult, InterpreterError> {
+    register_function(FunctionDef::from_definition(definition, current_source()));
+    Ok(InterpreterResult::Nil)
+}
+
+fn solve_variable(name: &str) -> Result<InterpreterResult, InterpreterError> {
+    let call_stack = CALL_STACK.lock().expect("Could not resolve CALL_STACK");
+    call_stack
+        .last()
+        .and_then(|frame| frame.variables.iter().find(|(variable, _)| variable == name))
+        .map(|(_, value)| value.clone())
+        .ok_or(InterpreterError::UndefinedVariable(name.to_string()))
+}
+
 fn solve_function_call(
     expressions: &Vec<Expression>,
     label: String,
 ) -> Result<InterpreterResult, InterpreterError> {
-    let functions = FUNCTIONS.lock().expect("Could not resolve FUNCTIONS");
-    if let Some(function) = functions.iter().find(|function| function.name == label) {
-        println!("Calling function {}", label);
-        let mut arguments: Vec<InterpreterResult> = vec![];
-        for expression in expressions {
-            match interpret_expression(expression) {
-                Ok(result) => arguments.push(result),
-                Err(error) => return Err(error),
-            }
+    // FUNCTIONS is released before running instructions so functions can call each other
+    let (parameters, instructions) = {
+        let functions = FUNCTIONS.lock().expect("Could not resolve FUNCTIONS");
+        match functions.iter().find(|function| function.name == label) {
+            Some(function) => (
+                function.arguments.iter().map(|argument| argument.name.clone()).collect::<Vec<String>>(),
+                function.instructions.clone(),
+            ),
+            None => return Err(InterpreterError::FunctionDoesNotExist),
         }
-        match &function.instructions {
-            super::functions::InstructionsDef::Expressions(instructions) => {
-                let mut result = InterpreterResult::Nil;
-                for instruction in instructions {
-                    match interpret_expression(instruction) {
-                        Ok(instruction_result) => result = instruction_result,
-                        Err(error) => return Err(error),
-                    }
-                }
-                println!("User function result : {:?}", result);
-                Ok(result)
-            },
-            super::functions::InstructionsDef::NativeFunction(native_function) => {
-                match native_function(&arguments) {
-                    Ok(result) => Ok(result),
-                    Err(error) => Err(InterpreterError::InvalidNativeFunction(error)),
-                }
+    };
+    let mut arguments: Vec<InterpreterResult> = vec![];
+    for expression in expressions {
+        match interpret_expression(expression) {
+            Ok(result) => arguments.push(result),
+            Err(error) => return Err(error),
+        }
+    }
+    enter_function(&label);
+    let result = execute_function(label, parameters, &instructions, arguments);
+    exit_function();
+    result
+}
+
+fn execute_function(
+    label: String,
+    parameters: Vec<String>,
+    instructions: &InstructionsDef,
+    arguments: Vec<InterpreterResult>,
+) -> Result<InterpreterResult, InterpreterError> {
+    match instructions {
+        InstructionsDef::Expressions(instructions, lines, source) => {
+            if arguments.len() != parameters.len() {
+                return Err(InterpreterError::ExpectedArgumentsCount(parameters.len()));
             }
+            let depth = CALL_STACK.lock().expect("Could not resolve CALL_STACK").len();
+            check_call_depth(depth).map_err(InterpreterError::LimitExceeded)?;
+            CALL_STACK.lock().expect("Could not resolve CALL_STACK").push(CallFrame {
+                function: label.clone(),
+                variables: parameters.into_iter().zip(arguments).collect(),
+            });
+            let result = solve_instructions(&label, instructions, lines, source);
+            CALL_STACK.lock().expect("Could not resolve CALL_STACK").pop();
+            result
+        },
+        InstructionsDef::NativeFunction(native_function) => {
+            match native_function(&arguments) {
+                Ok(result) => Ok(result),
+                Err(error) => Err(InterpreterError::InvalidNativeFunction(error)),
+            }
+        }
+    }
+}
+
+fn solve_instructions(
+    function: &str,
+    instructions: &[Expression],
+    lines: &[u32],
+    source: &Arc<[String]>,
+) -> Result<InterpreterResult, InterpreterError> {
+    let mut result = InterpreterResult::Nil;
+    for (index, instruction) in instructions.iter().enumerate() {
+        debug_statement(function, lines.get(index).copied().unwrap_or_default(), index, source);
+        match interpret_expression(instruction) {
+            Ok(instruction_result) => result = instruction_result,
+            Err(error) => return Err(error),
         }
-    } else {
-        Err(InterpreterError::FunctionDoesNotExist)
     }
+    Ok(result)
 }
 
 fn solve_operation(
@@ -396,6 +506,286 @@ fn solve_primary(primary: &Primary) -> Result<InterpreterResult, InterpreterErro
         Primary::True => Ok(InterpreterResult::Bool(true)),
         Primary::False => Ok(InterpreterResult::Bool(false)),
         Primary::Nil => Ok(InterpreterResult::Nil),
+        Primary::Variable(name) => solve_variable(name),
         Primary::Eof => Err(InterpreterError::EofShouldNotBeInterpreted),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs,
+        sync::Mutex,
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    };
+
+    use super::{interpret_expression, InterpreterError, InterpreterResult, CALL_STACK};
+    use crate::editor::{
+        debugger::{
+            clear_breakpoints, debug_statement, end_debug_run, send_debug_command, set_debug_mode, start_debug_run,
+            toggle_breakpoint, Breakpoint, DebugCommand, DEBUGGER_STATE, TOP_LEVEL_FUNCTION,
+        },
+        grammar::{resolve_ast, Expression, Function, Operation, Primary, Unary},
+        limits::{cancel_run, set_limits, start_run, LimitError},
+        profiler::{export_folded_stacks, profile_report},
+        tokenizer::get_prompt_tokens,
+    };
+
+    /// Runs share the call stack and the limits, tests running scripts take turns.
+    static SCRIPT_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Steps, call depth and allocations allowed by default.
+    const DEFAULT_LIMITS: (usize, usize, usize) = (100_000, 100, 1_000_000);
+
+    fn run(source: &str) -> Result<InterpreterResult, InterpreterError> {
+        run_with_limits(source, DEFAULT_LIMITS)
+    }
+
+    /// Runs a script like the editor does, returning the result of its last expression.
+    fn run_with_limits(
+        source: &str,
+        (max_steps, max_call_depth, max_allocations): (usize, usize, usize),
+    ) -> Result<InterpreterResult, InterpreterError> {
+        let _script_lock = SCRIPT_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+        let tokens = get_prompt_tokens(source.to_string()).expect("Script should tokenize");
+        let ast = resolve_ast(tokens).expect("Script should parse");
+        let eof = Expression::Function(Function::Operation(Operation::Unary(Unary::Primary(Primary::Eof))));
+        set_limits(max_steps, max_call_depth, max_allocations);
+        start_run();
+        let mut result = InterpreterResult::Nil;
+        for expression in ast.tree.iter().filter(|expression| **expression != eof) {
+            result = interpret_expression(expression)?;
+        }
+        Ok(result)
+    }
+
+    #[test]
+    fn calls_user_functions_with_their_arguments() {
+        let result = run("fun addThree(a, b, c) { a + b + c } fun twice(value) { addThree(value, value, 0) } twice(21)");
+        assert!(matches!(result, Ok(InterpreterResult::Num(42.0))), "{:?}", result);
+        let result = run("fun greet(name) { \"hello \" + name } greet(\"wraith\")");
+        assert!(matches!(result, Ok(InterpreterResult::Str(text)) if text == "hello wraith"));
+        assert!(CALL_STACK.lock().expect("Could not resolve CALL_STACK").is_empty());
+    }
+
+    #[test]
+    fn redefining_a_function_replaces_it() {
+        let result = run("fun answer() { 1 } fun answer() { 2 } answer()");
+        assert!(matches!(result, Ok(InterpreterResult::Num(2.0))), "{:?}", result);
+    }
+
+    #[test]
+    fn variables_only_exist_in_their_call() {
+        let result = run("fun identity(kept) { kept } identity(1) kept");
+        assert!(matches!(result, Err(InterpreterError::UndefinedVariable(name)) if name == "kept"));
+        let result = run("fun outer(hidden) { inner() } fun inner() { hidden } outer(1)");
+        assert!(matches!(result, Err(InterpreterError::UndefinedVariable(name)) if name == "hidden"));
+        assert!(CALL_STACK.lock().expect("Could not resolve CALL_STACK").is_empty());
+    }
+
+    #[test]
+    fn checks_the_arguments_count_of_user_functions() {
+        let result = run("fun pair(a, b) { a } pair(1)");
+        assert!(matches!(result, Err(InterpreterError::ExpectedArgumentsCount(2))), "{:?}", result);
+    }
+
+    #[test]
+    fn stops_after_the_step_limit() {
+        let result = run_with_limits("1 2 3", (3, 100, 1_000_000));
+        assert!(matches!(result, Ok(InterpreterResult::Num(3.0))), "{:?}", result);
+        let result = run_with_limits("1 2 3 4", (3, 100, 1_000_000));
+        assert!(matches!(result, Err(InterpreterError::LimitExceeded(LimitError::StepLimit(3)))), "{:?}", result);
+        // Expressions of called functions count as steps too
+        let result = run_with_limits("fun count() { 1 2 3 } count()", (4, 100, 1_000_000));
+        assert!(matches!(result, Err(InterpreterError::LimitExceeded(LimitError::StepLimit(4)))), "{:?}", result);
+    }
+
+    #[test]
+    fn stops_infinite_recursion_at_the_call_depth_limit() {
+        let result = run_with_limits("fun spin() { spin() } spin()", (100_000, 5, 1_000_000));
+        assert!(matches!(result, Err(InterpreterError::LimitExceeded(LimitError::CallDepthLimit(5)))), "{:?}", result);
+        assert!(CALL_STACK.lock().expect("Could not resolve CALL_STACK").is_empty());
+        let result = run_with_limits("fun a() { b() } fun b() { c() } fun c() { 1 } a()", (100_000, 3, 1_000_000));
+        assert!(matches!(result, Ok(InterpreterResult::Num(1.0))), "{:?}", result);
+    }
+
+    #[test]
+    fn stops_after_the_allocation_budget() {
+        let result = run_with_limits("\"abc\" + \"def\"", (100_000, 100, 6));
+        assert!(matches!(result, Err(InterpreterError::LimitExceeded(LimitError::AllocationLimit(6)))), "{:?}", result);
+    }
+
+    /// Script whose functions are spread over several lines, to follow the debugger through them.
+    const DEBUGGED_SCRIPT: &str = "fun debuggedInner() {\n  1\n}\nfun debuggedOuter() {\n  debuggedInner()\n  2\n}\ndebuggedOuter()\n3";
+
+    type Pause = (String, u32, String);
+
+    /// Holds the script lock while a script runs under the debugger, and leaves the debugger
+    /// disabled without breakpoints, even when an assertion fails while the script is paused.
+    struct DebugSession {
+        script: Option<JoinHandle<()>>,
+        last_pause: Option<Pause>,
+        _script_lock: std::sync::MutexGuard<'static, ()>,
+    }
+
+    impl DebugSession {
+        fn new(debug_mode: bool, breakpoints: &[Breakpoint]) -> DebugSession {
+            let script_lock = SCRIPT_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+            set_debug_mode(debug_mode);
+            clear_breakpoints();
+            for breakpoint in breakpoints {
+                toggle_breakpoint(breakpoint.clone());
+            }
+            DebugSession { script: None, last_pause: None, _script_lock: script_lock }
+        }
+
+        /// Runs the prompt on a script thread, like the editor does.
+        fn run(&mut self, source: &'static str) {
+            if let Some(script) = self.script.take() {
+                script.join().expect("Script thread should not panic");
+            }
+            self.last_pause = None;
+            self.script = Some(thread::spawn(move || {
+                let tokens = get_prompt_tokens(source.to_string()).expect("Script should tokenize");
+                let ast = resolve_ast(tokens).expect("Script should parse");
+                let eof = Expression::Function(Function::Operation(Operation::Unary(Unary::Primary(Primary::Eof))));
+                set_limits(DEFAULT_LIMITS.0, DEFAULT_LIMITS.1, DEFAULT_LIMITS.2);
+                start_run();
+                let source_lines = start_debug_run(source);
+                for (index, expression) in ast.tree.iter().enumerate().filter(|(_, expression)| **expression != eof) {
+                    debug_statement(TOP_LEVEL_FUNCTION, ast.lines[index], index, &source_lines);
+                    interpret_expression(expression).expect("Script should run");
+                }
+                end_debug_run();
+            }));
+        }
+
+        /// Waits for the script to pause at a new location, returns None once it has finished.
+        fn next_pause(&mut self) -> Option<Pause> {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            loop {
+                let paused = DEBUGGER_STATE
+                    .lock()
+                    .expect("Failed to get debugger state")
+                    .paused
+                    .as_ref()
+                    .map(|location| (location.function.clone(), location.line, location.statement.clone()));
+                if paused.is_some() && paused != self.last_pause {
+                    self.last_pause = paused.clone();
+                    return paused;
+                }
+                if self.script.as_ref().is_none_or(|script| script.is_finished()) {
+                    return None;
+                }
+                assert!(Instant::now() < deadline, "Script neither paused nor finished");
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        fn resume(&mut self, command: DebugCommand) -> Option<Pause> {
+            send_debug_command(command);
+            self.next_pause()
+        }
+    }
+
+    impl Drop for DebugSession {
+        fn drop(&mut self) {
+            cancel_run();
+            set_debug_mode(false);
+            clear_breakpoints();
+            if let Some(script) = self.script.take() {
+                let _ = script.join();
+            }
+        }
+    }
+
+    fn pause(function: &str, line: u32, statement: &str) -> Option<Pause> {
+        Some((function.to_string(), line, statement.to_string()))
+    }
+
+    #[test]
+    fn steps_into_every_statement() {
+        let mut session = DebugSession::new(true, &[]);
+        session.run(DEBUGGED_SCRIPT);
+        assert_eq!(session.next_pause(), pause(TOP_LEVEL_FUNCTION, 0, "fun debuggedInner() {"));
+        assert_eq!(session.resume(DebugCommand::StepInto), pause(TOP_LEVEL_FUNCTION, 3, "fun debuggedOuter() {"));
+        assert_eq!(session.resume(DebugCommand::StepInto), pause(TOP_LEVEL_FUNCTION, 7, "debuggedOuter()"));
+        assert_eq!(session.resume(DebugCommand::StepInto), pause("debuggedOuter", 4, "  debuggedInner()"));
+        assert_eq!(session.resume(DebugCommand::StepInto), pause("debuggedInner", 1, "  1"));
+        assert_eq!(session.resume(DebugCommand::StepInto), pause("debuggedOuter", 5, "  2"));
+        assert_eq!(session.resume(DebugCommand::StepInto), pause(TOP_LEVEL_FUNCTION, 8, "3"));
+        assert_eq!(session.resume(DebugCommand::StepInto), None);
+    }
+
+    #[test]
+    fn steps_over_function_calls() {
+        let mut session = DebugSession::new(true, &[]);
+        session.run(DEBUGGED_SCRIPT);
+        assert_eq!(session.next_pause(), pause(TOP_LEVEL_FUNCTION, 0, "fun debuggedInner() {"));
+        assert_eq!(session.resume(DebugCommand::StepOver), pause(TOP_LEVEL_FUNCTION, 3, "fun debuggedOuter() {"));
+        assert_eq!(session.resume(DebugCommand::StepOver), pause(TOP_LEVEL_FUNCTION, 7, "debuggedOuter()"));
+        assert_eq!(session.resume(DebugCommand::StepOver), pause(TOP_LEVEL_FUNCTION, 8, "3"));
+        assert_eq!(session.resume(DebugCommand::StepOver), None);
+    }
+
+    #[test]
+    fn continues_to_the_next_breakpoint() {
+        let mut session = DebugSession::new(true, &[Breakpoint::Line(5)]);
+        session.run(DEBUGGED_SCRIPT);
+        assert_eq!(session.next_pause(), pause(TOP_LEVEL_FUNCTION, 0, "fun debuggedInner() {"));
+        assert_eq!(session.resume(DebugCommand::Continue), pause("debuggedOuter", 5, "  2"));
+        assert_eq!(session.resume(DebugCommand::Continue), None);
+    }
+
+    #[test]
+    fn pauses_at_line_and_function_breakpoints() {
+        let mut session = DebugSession::new(false, &[Breakpoint::Line(5)]);
+        session.run(DEBUGGED_SCRIPT);
+        assert_eq!(session.next_pause(), pause("debuggedOuter", 5, "  2"));
+        assert_eq!(session.resume(DebugCommand::Continue), None);
+        drop(session);
+
+        let mut session = DebugSession::new(false, &[Breakpoint::Function("debuggedInner".to_string())]);
+        session.run(DEBUGGED_SCRIPT);
+        assert_eq!(session.next_pause(), pause("debuggedInner", 1, "  1"));
+        assert_eq!(session.resume(DebugCommand::Continue), None);
+    }
+
+    #[test]
+    fn resolves_functions_against_the_prompt_defining_them() {
+        let mut session = DebugSession::new(false, &[Breakpoint::Line(1)]);
+        session.run("fun debuggedElsewhere() {\n  \"elsewhere\"\n}");
+        assert_eq!(session.next_pause(), None);
+        // Line 1 of this prompt is the call, not the body of the function defined above
+        session.run("0\ndebuggedElsewhere()\n2");
+        assert_eq!(session.next_pause(), pause(TOP_LEVEL_FUNCTION, 1, "debuggedElsewhere()"));
+        assert_eq!(session.resume(DebugCommand::StepInto), pause("debuggedElsewhere", 1, "  \"elsewhere\""));
+        assert_eq!(session.resume(DebugCommand::Continue), None);
+    }
+
+    /// Script profiling a function calling another one twice.
+    const PROFILED_SCRIPT: &str = "fun profiledInner() { 1 } fun profiledOuter() { profiledInner() profiledInner() } profile(true) profiledOuter() profile(false)";
+
+    #[test]
+    fn reports_profiled_functions_by_inclusive_time() {
+        run(PROFILED_SCRIPT).expect("Script should run");
+        let report = profile_report();
+        // The calls toggling the profiler are not part of the profile
+        assert_eq!(report.len(), 3, "{:?}", report);
+        let rows: Vec<Vec<&str>> = report[1..].iter().map(|row| row.split_whitespace().collect()).collect();
+        assert_eq!(rows[0][..2], ["profiledOuter", "1"]);
+        assert_eq!(rows[1][..2], ["profiledInner", "2"]);
+    }
+
+    #[test]
+    fn folds_the_stacks_of_nested_functions() {
+        run(PROFILED_SCRIPT).expect("Script should run");
+        let path = env::temp_dir().join(format!("coding_survivor_folded_stacks_{}", std::process::id()));
+        export_folded_stacks(path.to_str().expect("Temporary path should be valid")).expect("Stacks should export");
+        let content = fs::read_to_string(&path).expect("Stacks should be written");
+        fs::remove_file(&path).expect("Stacks should be removed");
+        let stacks: Vec<&str> = content.lines().filter_map(|line| line.rsplit_once(' ')).map(|(stack, _)| stack).collect();
+        assert_eq!(stacks, ["<prompt>;profiledOuter", "<prompt>;profiledOuter;profiledInner"]);
+    }
+}
@@ -1,20 +1,49 @@
-use std::{collections::VecDeque, fmt::Display, thread};
+use std::{collections::VecDeque, fmt::Display, sync::atomic::Ordering, thread};
 
 use raylib::ffi::{self, KeyboardKey};
 
 use crate::{
     editor::{
-        grammar::{resolve_ast, AstParseError},
-        interpreter::{interpret_expression, InterpreterResult},
+        completion::{complete, cycle_completion, reset_completion},
+        formatter::format_script,
+        debugger::{debug_statement, end_debug_run, send_debug_command, start_debug_run, DebugCommand, TOP_LEVEL_FUNCTION},
+        history::{end_search, history_next, history_previous, push_history, reverse_search, update_search_query},
+        recorder::{capture_statement, is_recording},
+        line_editing::{
+            copy_to_clipboard, delete_backward, delete_forward, insert_character, move_cursor_end, move_cursor_home,
+            move_cursor_left, move_cursor_right, move_word_left, move_word_right, paste_from_clipboard, set_buffer,
+        },
+        grammar::{resolve_ast, Ast, AstParseError},
+        interpreter::{interpret_expression, InterpreterError, InterpreterResult, SCRIPT_RUNNING},
+        limits::{cancel_run, start_run, LimitError},
         tokenizer::{get_prompt_tokens, TokenizerError},
     },
-    game_state::EDITOR_STATE,
+    game_state::{push_editor_error, EDITOR_OUTPUT, EDITOR_STATE},
     GET_EDITOR_STATE_ERROR,
 };
 
 pub const BACKSPACE: char = '\x08';
 pub const CARRIAGE_RETURN: char = '\x13';
 pub const ARROW_UP: char = '↑';
+pub const ARROW_DOWN: char = '↓';
+pub const TAB: char = '\t';
+pub const ESCAPE: char = '\x1b';
+pub const REVERSE_SEARCH: char = '\x12';
+pub const ARROW_LEFT: char = '←';
+pub const ARROW_RIGHT: char = '→';
+pub const WORD_LEFT: char = '⇐';
+pub const WORD_RIGHT: char = '⇒';
+pub const HOME: char = '⇱';
+pub const END: char = '⇲';
+pub const DELETE: char = '\x7f';
+pub const COPY: char = '\x03';
+pub const PASTE: char = '\x16';
+pub const FORMAT: char = '\x06';
+pub const DEBUG_CONTINUE: char = '\u{f705}';
+pub const DEBUG_STEP_OVER: char = '\u{f70a}';
+pub const DEBUG_STEP_INTO: char = '\u{f70b}';
+// Large enough for the deepest call depth the execution limits allow
+pub const SCRIPT_THREAD_STACK_SIZE: usize = 64 * 1024 * 1024;
 pub static mut KEYS_PRESSED: VecDeque<char> = VecDeque::new();
 
 #[allow(static_mut_refs)]
@@ -36,6 +65,36 @@ pub fn process_key_pressed() {
         unsafe { KEYS_PRESSED.push_front(CARRIAGE_RETURN) };
     } else if key == KeyboardKey::KEY_UP as i32 {
         unsafe { KEYS_PRESSED.push_front(ARROW_UP) };
+    } else if key == KeyboardKey::KEY_DOWN as i32 {
+        unsafe { KEYS_PRESSED.push_front(ARROW_DOWN) };
+    } else if key == KeyboardKey::KEY_TAB as i32 {
+        unsafe { KEYS_PRESSED.push_front(TAB) };
+    } else if key == KeyboardKey::KEY_ESCAPE as i32 {
+        unsafe { KEYS_PRESSED.push_front(ESCAPE) };
+    } else if key == KeyboardKey::KEY_R as i32 && is_control_down() {
+        unsafe { KEYS_PRESSED.push_front(REVERSE_SEARCH) };
+    } else if key == KeyboardKey::KEY_LEFT as i32 {
+        unsafe { KEYS_PRESSED.push_front(if is_control_down() { WORD_LEFT } else { ARROW_LEFT }) };
+    } else if key == KeyboardKey::KEY_RIGHT as i32 {
+        unsafe { KEYS_PRESSED.push_front(if is_control_down() { WORD_RIGHT } else { ARROW_RIGHT }) };
+    } else if key == KeyboardKey::KEY_HOME as i32 {
+        unsafe { KEYS_PRESSED.push_front(HOME) };
+    } else if key == KeyboardKey::KEY_END as i32 {
+        unsafe { KEYS_PRESSED.push_front(END) };
+    } else if key == KeyboardKey::KEY_DELETE as i32 {
+        unsafe { KEYS_PRESSED.push_front(DELETE) };
+    } else if key == KeyboardKey::KEY_C as i32 && is_control_down() {
+        unsafe { KEYS_PRESSED.push_front(COPY) };
+    } else if key == KeyboardKey::KEY_V as i32 && is_control_down() {
+        unsafe { KEYS_PRESSED.push_front(PASTE) };
+    } else if key == KeyboardKey::KEY_F as i32 && is_control_down() {
+        unsafe { KEYS_PRESSED.push_front(FORMAT) };
+    } else if key == KeyboardKey::KEY_F5 as i32 {
+        unsafe { KEYS_PRESSED.push_front(DEBUG_CONTINUE) };
+    } else if key == KeyboardKey::KEY_F10 as i32 {
+        unsafe { KEYS_PRESSED.push_front(DEBUG_STEP_OVER) };
+    } else if key == KeyboardKey::KEY_F11 as i32 {
+        unsafe { KEYS_PRESSED.push_front(DEBUG_STEP_INTO) };
     } else {
         //Process actual character in another thread to avoid performance loss
         thread::spawn(move || {
@@ -49,58 +108,118 @@ pub fn process_key_pressed() {
     }
 }
 
+fn is_control_down() -> bool {
+    unsafe {
+        ffi::IsKeyDown(KeyboardKey::KEY_LEFT_CONTROL as i32) || ffi::IsKeyDown(KeyboardKey::KEY_RIGHT_CONTROL as i32)
+    }
+}
+
 #[allow(static_mut_refs)]
 pub fn editor_processing() {
     thread::spawn(|| loop {
         let mut editor_state = EDITOR_STATE.lock().expect(GET_EDITOR_STATE_ERROR);
 
         if let Some(key) = unsafe { KEYS_PRESSED.pop_back() } {
-            match key {
-                BACKSPACE => {
-                    editor_state.buffer.pop();
-                }
-                ARROW_UP => {
-                    editor_state.buffer = vec![];
-                    if let Some(history) = editor_state.input_history.clone().last() {
-                        for character in history.chars() {
-                            editor_state.buffer.push(character);
-                        }
-                    };
-                }
-                CARRIAGE_RETURN => process_prompt(&mut editor_state),
-                _ => editor_state.buffer.push(key),
-            };
+            process_key(&mut editor_state, key);
+            flush_editor_output(&mut editor_state);
         }
     });
 }
 
+fn process_key(editor_state: &mut std::sync::MutexGuard<'_, crate::game_state::EditorState>, key: char) {
+    let debug_command = match key {
+        DEBUG_CONTINUE => Some(DebugCommand::Continue),
+        DEBUG_STEP_OVER => Some(DebugCommand::StepOver),
+        DEBUG_STEP_INTO => Some(DebugCommand::StepInto),
+        _ => None,
+    };
+    if let Some(debug_command) = debug_command {
+        send_debug_command(debug_command);
+        return;
+    }
+    // Ctrl+C cancels a running script, even while searching the history
+    if key == COPY && SCRIPT_RUNNING.load(Ordering::SeqCst) {
+        cancel_run();
+        return;
+    }
+    if editor_state.history_search.is_some() {
+        process_search_key(editor_state, key);
+        return;
+    }
+    match key {
+        TAB => complete(editor_state),
+        // Arrows walk the history unless Tab opened the completion popup
+        ARROW_UP if editor_state.completion.is_some() => cycle_completion(editor_state, -1),
+        ARROW_DOWN if editor_state.completion.is_some() => cycle_completion(editor_state, 1),
+        _ => process_editing_key(editor_state, key),
+    };
+}
+
+fn process_editing_key(editor_state: &mut std::sync::MutexGuard<'_, crate::game_state::EditorState>, key: char) {
+    reset_completion(editor_state);
+    match key {
+        BACKSPACE => delete_backward(editor_state),
+        DELETE => delete_forward(editor_state),
+        ARROW_LEFT => move_cursor_left(editor_state),
+        ARROW_RIGHT => move_cursor_right(editor_state),
+        WORD_LEFT => move_word_left(editor_state),
+        WORD_RIGHT => move_word_right(editor_state),
+        HOME => move_cursor_home(editor_state),
+        END => move_cursor_end(editor_state),
+        COPY => copy_to_clipboard(editor_state),
+        PASTE => paste_from_clipboard(editor_state),
+        FORMAT => format_buffer(editor_state),
+        ARROW_UP => history_previous(editor_state),
+        ARROW_DOWN => history_next(editor_state),
+        REVERSE_SEARCH => reverse_search(editor_state),
+        ESCAPE => (),
+        CARRIAGE_RETURN => process_prompt(editor_state),
+        _ => insert_character(editor_state, key),
+    };
+}
+
+fn process_search_key(editor_state: &mut std::sync::MutexGuard<'_, crate::game_state::EditorState>, key: char) {
+    match key {
+        REVERSE_SEARCH => reverse_search(editor_state),
+        BACKSPACE => update_search_query(editor_state, None),
+        CARRIAGE_RETURN => {
+            end_search(editor_state);
+            process_prompt(editor_state);
+        }
+        ESCAPE | TAB | ARROW_UP | ARROW_DOWN | ARROW_LEFT | ARROW_RIGHT | HOME | END => end_search(editor_state),
+        DELETE | WORD_LEFT | WORD_RIGHT | COPY | PASTE | FORMAT => (),
+        _ => update_search_query(editor_state, Some(key)),
+    };
+}
+
+fn format_buffer(editor_state: &mut std::sync::MutexGuard<'_, crate::game_state::EditorState>) {
+    let source: String = editor_state.buffer.iter().collect();
+    match format_script(&source) {
+        Ok(formatted) => set_buffer(editor_state, formatted.trim_end().chars().collect()),
+        Err(error) => editor_error_message(editor_state, &format!("Could not format the prompt : {:?}", error)),
+    }
+}
+
 fn process_prompt(editor_state: &mut std::sync::MutexGuard<'_, crate::game_state::EditorState>) {
+    if SCRIPT_RUNNING.load(Ordering::SeqCst) {
+        editor_error_message(editor_state, &"A script is already running, wait for it to finish");
+        return;
+    }
     let prompt: String = editor_state.buffer.iter().collect();
-    editor_state.buffer = vec![];
+    set_buffer(editor_state, vec![]);
     editor_state.commands.push(prompt.clone());
-    editor_state.input_history.push(prompt.clone());
+    push_history(editor_state, &prompt);
     let tokens = get_prompt_tokens(prompt.clone());
-    println!("Tokens for the command :");
-    tokens.iter().for_each(|token| {
-        println!("{:?}", token);
-    });
     match tokens {
         Ok(tokens) => {
-            println!("AST Expressions for the command :");
             match resolve_ast(tokens) {
-                Ok(ast) => ast.tree.iter().for_each(|expression| {
-                    println!("{:?}", expression);
-                    match interpret_expression(expression) {
-                        Ok(result) => match result {
-                            InterpreterResult::Num(num_result) => editor_result_message(editor_state, &num_result),
-                            InterpreterResult::Str(str_result) => editor_result_message(editor_state, &str_result),
-                            InterpreterResult::Bool(bool_result) => editor_result_message(editor_state, &bool_result),
-                            InterpreterResult::Nil => (),
-                            _ => println!("Unexpected expression result"),
-                        },
-                        Err(error) => println!("{:?}", error),
-                    }
-                }),
+                Ok(ast) => {
+                    SCRIPT_RUNNING.store(true, Ordering::SeqCst);
+                    thread::Builder::new()
+                        .stack_size(SCRIPT_THREAD_STACK_SIZE)
+                        .spawn(move || run_script(ast, &prompt))
+                        .expect("Failed to start script thread");
+                }
                 Err(error) => match error {
                     AstParseError::TokenInvalidGrammar => editor_error_message(editor_state, &"Invalid grammar for provided command"),
                     AstParseError::MissingLiteralForNumber => editor_error_message(editor_state, &"Missing value for parsed number"),
@@ -110,6 +229,8 @@ fn process_prompt(editor_state: &mut std::sync::MutexGuard<'_, crate::game_state
                     AstParseError::InvalidFactorExpressions => editor_error_message(editor_state, &"Invalid values passed to operation"),
                     AstParseError::LabelWithNoValidNextToken => editor_error_message(editor_state, &"Invalid values passed after label"),
                     AstParseError::InvalidTokensInGroup => editor_error_message(editor_state, &"Invalid values passed to () group"),
+                    AstParseError::InvalidFunctionDefinition => editor_error_message(editor_state, &"Invalid function definition, expected fun name(parameters) { instructions }"),
+                    AstParseError::UnclosedBlock => editor_error_message(editor_state, &"Missing } to close the block"),
                 },
             }
         }
@@ -119,15 +240,75 @@ fn process_prompt(editor_state: &mut std::sync::MutexGuard<'_, crate::game_state
             TokenizerError::IdentifierMissmatch => editor_error_message(editor_state, &"Invalid identifier, use a valid keyword instead"),
             TokenizerError::InvalidFunctionSyntax => editor_error_message(editor_state, &"Invalid function syntax"),
             TokenizerError::NoIdentifierNorFunctionError => editor_error_message(editor_state, &"No matching keyword nor function"),
+            TokenizerError::UnterminatedComment => editor_error_message(editor_state, &"Invalid comment, any /* must match a */"),
         },
     };
 }
 
-fn editor_result_message(
-    editor_state: &mut std::sync::MutexGuard<'_, crate::game_state::EditorState>,
-    message: &dyn Display,
-) {
-    editor_state.commands.push(format!("RES-Result : {}", message));
+/// Runs the expressions on the script thread, so the editor and the map keep updating while
+/// the script runs or is paused by the debugger.
+fn run_script(ast: Ast, source: &str) {
+    start_run();
+    let source_lines = start_debug_run(source);
+    let mut recorded_statements = 0;
+    for (index, expression) in ast.tree.iter().enumerate() {
+        debug_statement(TOP_LEVEL_FUNCTION, ast.lines.get(index).copied().unwrap_or_default(), index, &source_lines);
+        // The call to record() is not part of the recording, and the call to stop() ends it
+        let was_recording = is_recording();
+        let result = interpret_expression(expression);
+        if result.is_ok() && was_recording {
+            let statement = statement_source(source, &ast.starts, index);
+            if capture_statement(expression, statement, recorded_statements == 0) {
+                recorded_statements += 1;
+            }
+        }
+        let failed = result.is_err();
+        match result {
+            Ok(InterpreterResult::Nil) => (),
+            Ok(InterpreterResult::Str(text)) => push_editor_result(&text),
+            Ok(result) => push_editor_result(&result),
+            Err(error) => push_editor_error(&interpreter_error_message(&error)),
+        }
+        flush_editor_output(&mut EDITOR_STATE.lock().expect(GET_EDITOR_STATE_ERROR));
+        if failed {
+            break;
+        }
+    }
+    end_debug_run();
+    SCRIPT_RUNNING.store(false, Ordering::SeqCst);
+}
+
+/// Source of the statement at the index, up to the start of the next statement.
+fn statement_source<'a>(source: &'a str, starts: &[usize], index: usize) -> &'a str {
+    let start = starts.get(index).copied().unwrap_or(source.len());
+    let end = starts.get(index + 1).copied().unwrap_or(source.len());
+    source.get(start..end).unwrap_or_default().trim()
+}
+
+pub fn interpreter_error_message(error: &InterpreterError) -> String {
+    match error {
+        InterpreterError::LimitExceeded(LimitError::StepLimit(max_steps)) => format!("Script stopped after {} steps, raise the limit with limits(steps, depth, allocations)", max_steps),
+        InterpreterError::LimitExceeded(LimitError::CallDepthLimit(max_call_depth)) => format!("Script stopped at call depth {}, check for infinite recursion or raise the limit with limits(steps, depth, allocations)", max_call_depth),
+        InterpreterError::LimitExceeded(LimitError::AllocationLimit(max_allocations)) => format!("Script stopped after allocating {} values, raise the budget with limits(steps, depth, allocations)", max_allocations),
+        InterpreterError::LimitExceeded(LimitError::Cancelled) => "Script cancelled".to_string(),
+        InterpreterError::FunctionDoesNotExist => "Called function does not exist".to_string(),
+        InterpreterError::ExpectedArgumentsCount(count) => format!("Function expects {} arguments", count),
+        InterpreterError::UndefinedVariable(name) => format!("Undefined variable {}", name),
+        InterpreterError::InvalidOperationValues => "Invalid values passed to operation".to_string(),
+        InterpreterError::InvalidNativeFunction(error) => format!("Function failed : {:?}", error),
+        InterpreterError::ExpressionNotHandled
+        | InterpreterError::EofShouldNotBeInterpreted
+        | InterpreterError::UnexpectedLatelyInterpretedBang => format!("Unexpected interpreter error : {:?}", error),
+    }
+}
+
+fn flush_editor_output(editor_state: &mut std::sync::MutexGuard<'_, crate::game_state::EditorState>) {
+    let mut output = EDITOR_OUTPUT.lock().expect("Failed to get editor output");
+    editor_state.commands.append(&mut output);
+}
+
+fn push_editor_result(result: &dyn Display) {
+    EDITOR_OUTPUT.lock().expect("Failed to get editor output").push(format!("RES-Result : {}", result));
 }
 
 fn editor_error_message(
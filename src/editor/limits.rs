@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Highest call depth that can be configured, bounded by the stack of the script thread.
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// Expressions a run may evaluate, including the ones of called functions.
+static MAX_STEPS: AtomicUsize = AtomicUsize::new(100_000);
+/// User function calls a run may nest.
+static MAX_RUN_CALL_DEPTH: AtomicUsize = AtomicUsize::new(100);
+/// Allocation budget of a run: every value produced counts against it, even once dropped, strings
+/// counting one per character. It bounds the work spent building values, not the memory in use.
+static MAX_ALLOCATIONS: AtomicUsize = AtomicUsize::new(1_000_000);
+
+/// Set from the editor to stop the running script at its next step.
+pub static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+static STEPS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+pub enum LimitError {
+    StepLimit(usize),
+    CallDepthLimit(usize),
+    AllocationLimit(usize),
+    Cancelled,
+}
+
+pub fn start_run() {
+    STEPS.store(0, Ordering::SeqCst);
+    ALLOCATIONS.store(0, Ordering::SeqCst);
+    CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+}
+
+pub fn cancel_run() {
+    CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_cancel_requested() -> bool {
+    CANCEL_REQUESTED.load(Ordering::SeqCst)
+}
+
+pub fn set_limits(max_steps: usize, max_call_depth: usize, max_allocations: usize) {
+    MAX_STEPS.store(max_steps, Ordering::SeqCst);
+    MAX_RUN_CALL_DEPTH.store(max_call_depth, Ordering::SeqCst);
+    MAX_ALLOCATIONS.store(max_allocations, Ordering::SeqCst);
+}
+
+pub fn count_step() -> Result<(), LimitError> {
+    if is_cancel_requested() {
+        return Err(LimitError::Cancelled);
+    }
+    let max_steps = MAX_STEPS.load(Ordering::SeqCst);
+    if STEPS.fetch_add(1, Ordering::SeqCst) >= max_steps {
+        return Err(LimitError::StepLimit(max_steps));
+    }
+    Ok(())
+}
+
+pub fn count_allocations(count: usize) -> Result<(), LimitError> {
+    let max_allocations = MAX_ALLOCATIONS.load(Ordering::SeqCst);
+    if ALLOCATIONS.fetch_add(count, Ordering::SeqCst) + count > max_allocations {
+        return Err(LimitError::AllocationLimit(max_allocations));
+    }
+    Ok(())
+}
+
+pub fn check_call_depth(depth: usize) -> Result<(), LimitError> {
+    let max_call_depth = MAX_RUN_CALL_DEPTH.load(Ordering::SeqCst);
+    if depth >= max_call_depth {
+        return Err(LimitError::CallDepthLimit(max_call_depth));
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::ffi::{CStr, CString};
+
+use raylib::ffi;
+
+use crate::game_state::{push_editor_error, EditorState};
+
+pub fn set_buffer(editor_state: &mut EditorState, buffer: Vec<char>) {
+    editor_state.cursor = buffer.len();
+    editor_state.buffer = buffer;
+}
+
+pub fn insert_character(editor_state: &mut EditorState, character: char) {
+    let cursor = editor_state.cursor.min(editor_state.buffer.len());
+    editor_state.buffer.insert(cursor, character);
+    editor_state.cursor = cursor + 1;
+}
+
+pub fn insert_text(editor_state: &mut EditorState, text: &str) {
+    for character in text.chars() {
+        if character.is_control() {
+            insert_character(editor_state, ' ');
+        } else {
+            insert_character(editor_state, character);
+        }
+    }
+}
+
+pub fn delete_backward(editor_state: &mut EditorState) {
+    if editor_state.cursor == 0 {
+        return;
+    }
+    editor_state.cursor -= 1;
+    editor_state.buffer.remove(editor_state.cursor);
+}
+
+pub fn delete_forward(editor_state: &mut EditorState) {
+    if editor_state.cursor < editor_state.buffer.len() {
+        editor_state.buffer.remove(editor_state.cursor);
+    }
+}
+
+pub fn move_cursor_left(editor_state: &mut EditorState) {
+    editor_state.cursor = editor_state.cursor.saturating_sub(1);
+}
+
+pub fn move_cursor_right(editor_state: &mut EditorState) {
+    editor_state.cursor = (editor_state.cursor + 1).min(editor_state.buffer.len());
+}
+
+pub fn move_cursor_home(editor_state: &mut EditorState) {
+    editor_state.cursor = 0;
+}
+
+pub fn move_cursor_end(editor_state: &mut EditorState) {
+    editor_state.cursor = editor_state.buffer.len();
+}
+
+/// Moves to the start of the previous word, skipping any separator right before the cursor.
+pub fn move_word_left(editor_state: &mut EditorState) {
+    let buffer = &editor_state.buffer;
+    let mut cursor = editor_state.cursor;
+    while cursor > 0 && !buffer[cursor - 1].is_alphanumeric() {
+        cursor -= 1;
+    }
+    while cursor > 0 && buffer[cursor - 1].is_alphanumeric() {
+        cursor -= 1;
+    }
+    editor_state.cursor = cursor;
+}
+
+/// Moves past the end of the next word, skipping any separator right after the cursor.
+pub fn move_word_right(editor_state: &mut EditorState) {
+    let buffer = &editor_state.buffer;
+    let mut cursor = editor_state.cursor;
+    while cursor < buffer.len() && !buffer[cursor].is_alphanumeric() {
+        cursor += 1;
+    }
+    while cursor < buffer.len() && buffer[cursor].is_alphanumeric() {
+        cursor += 1;
+    }
+    editor_state.cursor = cursor;
+}
+
+pub fn paste_from_clipboard(editor_state: &mut EditorState) {
+    let clipboard = unsafe { ffi::GetClipboardText() };
+    if clipboard.is_null() {
+        return;
+    }
+    let text = unsafe { CStr::from_ptr(clipboard) }.to_string_lossy().to_string();
+    insert_text(editor_state, &text);
+}
+
+pub fn copy_to_clipboard(editor_state: &EditorState) {
+    let text: String = editor_state.buffer.iter().collect();
+    match CString::new(text) {
+        Ok(text) => unsafe { ffi::SetClipboardText(text.as_ptr()) },
+        Err(error) => push_editor_error(&format!("Failed to copy prompt to clipboard : {}", error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        delete_backward, delete_forward, insert_character, insert_text, move_cursor_end, move_cursor_home,
+        move_cursor_left, move_cursor_right, move_word_left, move_word_right, set_buffer,
+    };
+    use crate::game_state::EditorState;
+
+    fn editor_with_buffer(buffer: &str) -> EditorState {
+        let mut editor_state = EditorState::empty();
+        set_buffer(&mut editor_state, buffer.chars().collect());
+        editor_state
+    }
+
+    fn buffer(editor_state: &EditorState) -> String {
+        editor_state.buffer.iter().collect()
+    }
+
+    #[test]
+    fn jumps_over_words() {
+        let mut editor_state = editor_with_buffer("moveUp()  breakDown(\"up\")");
+        move_word_left(&mut editor_state);
+        assert_eq!(editor_state.cursor, 21);
+        move_word_left(&mut editor_state);
+        assert_eq!(editor_state.cursor, 10);
+        move_word_left(&mut editor_state);
+        move_word_left(&mut editor_state);
+        assert_eq!(editor_state.cursor, 0);
+        move_word_right(&mut editor_state);
+        assert_eq!(editor_state.cursor, 6);
+        move_word_right(&mut editor_state);
+        assert_eq!(editor_state.cursor, 19);
+        move_cursor_end(&mut editor_state);
+        move_word_right(&mut editor_state);
+        assert_eq!(editor_state.cursor, editor_state.buffer.len());
+    }
+
+    #[test]
+    fn edits_at_the_cursor() {
+        let mut editor_state = editor_with_buffer("moveU()");
+        move_cursor_left(&mut editor_state);
+        move_cursor_left(&mut editor_state);
+        insert_character(&mut editor_state, 'p');
+        assert_eq!(buffer(&editor_state), "moveUp()");
+        assert_eq!(editor_state.cursor, 6);
+        delete_backward(&mut editor_state);
+        delete_backward(&mut editor_state);
+        insert_text(&mut editor_state, "Do\nwn");
+        assert_eq!(buffer(&editor_state), "moveDo wn()");
+        move_cursor_home(&mut editor_state);
+        delete_forward(&mut editor_state);
+        assert_eq!(buffer(&editor_state), "oveDo wn()");
+        delete_backward(&mut editor_state);
+        assert_eq!(editor_state.cursor, 0);
+    }
+
+    #[test]
+    fn keeps_the_cursor_in_the_buffer() {
+        let mut editor_state = editor_with_buffer("loot()");
+        move_cursor_right(&mut editor_state);
+        assert_eq!(editor_state.cursor, 6);
+        delete_forward(&mut editor_state);
+        assert_eq!(buffer(&editor_state), "loot()");
+        move_cursor_home(&mut editor_state);
+        move_cursor_left(&mut editor_state);
+        assert_eq!(editor_state.cursor, 0);
+    }
+}
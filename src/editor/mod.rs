@@ -1,5 +1,26 @@
+#[cfg(feature = "game")]
+pub mod completion;
+#[cfg(feature = "game")]
+pub mod debugger;
+pub mod formatter;
 pub mod functions;
 pub mod grammar;
+#[cfg(feature = "game")]
+pub mod highlight;
+#[cfg(feature = "game")]
+pub mod history;
+#[cfg(feature = "game")]
 pub mod interpreter;
+#[cfg(feature = "game")]
 pub mod keyboard;
+#[cfg(feature = "game")]
+pub mod limits;
+#[cfg(feature = "game")]
+pub mod line_editing;
+#[cfg(feature = "game")]
+pub mod natives;
+#[cfg(feature = "game")]
+pub mod profiler;
+#[cfg(feature = "game")]
+pub mod recorder;
 pub mod tokenizer;
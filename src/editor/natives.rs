@@ -0,0 +1,432 @@
+use std::io;
+
+use raylib::color::Color;
+
+use crate::{editor::{debugger::{clear_breakpoints, set_debug_mode, toggle_breakpoint, Breakpoint}, profiler::{export_folded_stacks, profile_report, record_game_action, set_profiling, GameAction}, recorder::{start_recording, stop_recording, RecordError}, limits::{set_limits, MAX_CALL_DEPTH}, highlight::{set_theme, set_theme_color, ThemeError}}, item::recipe::RECIPES, pathfinding::PathError, game_state::{get_tile_string, AttackError, BreakError, CraftError, Direction, DrinkError, EatError, EquipError, MoveError, PlaceError, EDITOR_OUTPUT, MAP_STATE}};
+
+use super::{functions::{InstructionsDef, FUNCTIONS}, interpreter::InterpreterResult};
+
+pub type NativeFunction = fn(&Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError>;
+
+#[derive(Debug)]
+pub enum FunctionError {
+    ExpectedArgumentsCount(usize),
+    PlayerMoveError(MoveError),
+    BreakSomethingError(BreakError),
+    EquipToolError(EquipError),
+    CraftItemError(CraftError),
+    PlaceItemError(PlaceError),
+    EatFoodError(EatError),
+    DrinkWaterError(DrinkError),
+    UnknownDirection(String),
+    AttackCreatureError(AttackError),
+    FindPathError(PathError),
+    NothingToLoot,
+    ExpectedStringArgument(usize),
+    ExpectedNumberArgument(usize),
+    EditorThemeError(ThemeError),
+    UnknownFunction(String),
+    ExpectedBoolArgument(usize),
+    InvalidBreakpoint,
+    ProfileExportError(io::Error),
+    RecordingError(RecordError),
+    ReservedFunctionName(String),
+}
+
+pub(super) fn move_down(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    move_player(arguments, Direction::Down)
+}
+
+pub(super) fn move_up(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    move_player(arguments, Direction::Up)
+}
+
+pub(super) fn move_left(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    move_player(arguments, Direction::Left)
+}
+
+pub(super) fn move_right(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    move_player(arguments, Direction::Right)
+}
+
+fn expected_empty_arguments(arguments: &Vec<InterpreterResult>) -> Result<(), FunctionError> {
+    if !arguments.is_empty() {
+        return Err(FunctionError::ExpectedArgumentsCount(0));
+    }
+    Ok(())
+}
+
+fn expected_arguments_count(arguments: &[InterpreterResult], count: usize) -> Result<(), FunctionError> {
+    if arguments.len() != count {
+        return Err(FunctionError::ExpectedArgumentsCount(count));
+    }
+    Ok(())
+}
+
+fn string_argument(arguments: &[InterpreterResult], index: usize) -> Result<String, FunctionError> {
+    match arguments.get(index) {
+        Some(InterpreterResult::Str(value)) => Ok(value.clone()),
+        _ => Err(FunctionError::ExpectedStringArgument(index)),
+    }
+}
+
+fn number_argument(arguments: &[InterpreterResult], index: usize) -> Result<f64, FunctionError> {
+    match arguments.get(index) {
+        Some(InterpreterResult::Num(value)) => Ok(*value),
+        _ => Err(FunctionError::ExpectedNumberArgument(index)),
+    }
+}
+
+fn bool_argument(arguments: &[InterpreterResult], index: usize) -> Result<bool, FunctionError> {
+    match arguments.get(index) {
+        Some(InterpreterResult::Bool(value)) => Ok(*value),
+        _ => Err(FunctionError::ExpectedBoolArgument(index)),
+    }
+}
+
+fn move_player(
+    arguments: &Vec<InterpreterResult>,
+    direction: Direction,
+) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    match map_state.may_move_player(direction) {
+        Ok(_) => {
+            record_game_action(GameAction::Move);
+            Ok(InterpreterResult::Nil)
+        }
+        Err(error) => Err(FunctionError::PlayerMoveError(error)),
+    }
+}
+
+pub(super) fn break_down(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    break_something(arguments, Direction::Down)
+}
+
+pub(super) fn break_up(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    break_something(arguments, Direction::Up)
+}
+
+pub(super) fn break_left(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    break_something(arguments, Direction::Left)
+}
+
+pub(super) fn break_right(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    break_something(arguments, Direction::Right)
+}
+
+fn direction_argument(arguments: &[InterpreterResult], index: usize) -> Result<Direction, FunctionError> {
+    let name = string_argument(arguments, index)?;
+    Direction::from_name(&name).ok_or(FunctionError::UnknownDirection(name))
+}
+
+fn path_argument(arguments: &[InterpreterResult]) -> Result<Vec<Direction>, FunctionError> {
+    expected_arguments_count(arguments, 2)?;
+    let x = number_argument(arguments, 0)?.floor() as i32;
+    let y = number_argument(arguments, 1)?.floor() as i32;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    map_state.find_path((x, y)).map_err(FunctionError::FindPathError)
+}
+
+pub(super) fn path_to(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    let path = path_argument(arguments)?;
+    let names: Vec<&str> = path.iter().map(Direction::get_name).collect();
+    Ok(InterpreterResult::Str(names.join(",")))
+}
+
+pub(super) fn go_to(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    let path = path_argument(arguments)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    map_state.path = path.into_iter().collect();
+    Ok(InterpreterResult::Num(map_state.path.len() as f64))
+}
+
+pub(super) fn attack(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    let direction = direction_argument(arguments, 0)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    map_state.may_attack(direction).map_err(FunctionError::AttackCreatureError)?;
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn look(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    let direction = direction_argument(arguments, 0)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    let target = direction.next_to(map_state.player.position);
+    let (x, y) = (target.x as i32, target.y as i32);
+    let seen = match map_state.creature_at(x, y) {
+        Some(index) => map_state.creatures[index].kind.get_name().to_string(),
+        None => get_tile_string(map_state.world.tile(x, y)),
+    };
+    Ok(InterpreterResult::Str(seen))
+}
+
+pub(super) fn biome(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    let position = map_state.player.position;
+    let biome = map_state.world.biome(position.x as i32, position.y as i32);
+    Ok(InterpreterResult::Str(biome.get_name().to_string()))
+}
+
+pub(super) fn health(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let map_state = MAP_STATE.lock().expect("Failed to get map state");
+    Ok(InterpreterResult::Num(map_state.player.health as f64))
+}
+
+pub(super) fn hunger(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let map_state = MAP_STATE.lock().expect("Failed to get map state");
+    Ok(InterpreterResult::Num(map_state.player.hunger as f64))
+}
+
+pub(super) fn thirst(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let map_state = MAP_STATE.lock().expect("Failed to get map state");
+    Ok(InterpreterResult::Num(map_state.player.thirst as f64))
+}
+
+pub(super) fn eat(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    let id = string_argument(arguments, 0)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    map_state.player.eat(&id).map_err(FunctionError::EatFoodError)?;
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn drink(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    map_state.may_drink().map_err(FunctionError::DrinkWaterError)?;
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn time(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let map_state = MAP_STATE.lock().expect("Failed to get map state");
+    Ok(InterpreterResult::Num(map_state.clock.hour() as f64))
+}
+
+pub(super) fn inventory(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    map_state.is_inventory_toggled = !map_state.is_inventory_toggled;
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn equip(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    let id = string_argument(arguments, 0)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    map_state.player.equip(&id).map_err(FunctionError::EquipToolError)?;
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn place(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 2)?;
+    let direction = direction_argument(arguments, 0)?;
+    let id = string_argument(arguments, 1)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    map_state.may_place_item(direction, &id).map_err(FunctionError::PlaceItemError)?;
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn craft(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 2)?;
+    let id = string_argument(arguments, 0)?;
+    let times = number_argument(arguments, 1)? as i32;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    map_state.player.craft(&id, times).map_err(FunctionError::CraftItemError)?;
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn can_craft(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    let id = string_argument(arguments, 0)?;
+    let map_state = MAP_STATE.lock().expect("Failed to get map state");
+    let times = map_state.player.craftable_times(&id).map_err(FunctionError::CraftItemError)?;
+    Ok(InterpreterResult::Bool(times > 0))
+}
+
+pub(super) fn recipes(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let map_state = MAP_STATE.lock().expect("Failed to get map state");
+    let mut output = EDITOR_OUTPUT.lock().expect("Failed to get editor output");
+    for recipe in RECIPES.recipes() {
+        let craftable = map_state.player.craftable_times(&recipe.output).unwrap_or(0) > 0;
+        let marker = if craftable { "* " } else { "  " };
+        output.push(format!("{}{}", marker, recipe.to_display_string()));
+    }
+    Ok(InterpreterResult::Nil)
+}
+
+fn break_something(
+    arguments: &Vec<InterpreterResult>,
+    direction: Direction,
+) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to get map state");
+    match map_state.may_break_something(direction) {
+        Ok(_) => {
+            record_game_action(GameAction::Break);
+            Ok(InterpreterResult::Nil)
+        }
+        Err(error) => Err(FunctionError::BreakSomethingError(error)),
+    }
+}
+
+pub(super) fn zoom_out(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to load map state");
+    if map_state.zoom > 0.1 {
+        map_state.zoom -= 0.1;
+    }
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn zoom_in(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to load map state");
+    if map_state.zoom < 2.0 {
+        map_state.zoom += 0.1;
+    }
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn loot(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let mut map_state = MAP_STATE.lock().expect("Failed to load map state");
+    if !map_state.loot() {
+        return Err(FunctionError::NothingToLoot);
+    }
+    record_game_action(GameAction::Loot);
+    Ok(InterpreterResult::Nil)
+}
+
+
+pub(super) fn theme(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    let name = string_argument(arguments, 0)?;
+    match set_theme(&name) {
+        Ok(_) => Ok(InterpreterResult::Nil),
+        Err(error) => Err(FunctionError::EditorThemeError(error)),
+    }
+}
+
+pub(super) fn theme_color(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 4)?;
+    let kind = string_argument(arguments, 0)?;
+    let red = number_argument(arguments, 1)?.clamp(0.0, 255.0) as u8;
+    let green = number_argument(arguments, 2)?.clamp(0.0, 255.0) as u8;
+    let blue = number_argument(arguments, 3)?.clamp(0.0, 255.0) as u8;
+    match set_theme_color(&kind, Color::new(red, green, blue, 255)) {
+        Ok(_) => Ok(InterpreterResult::Nil),
+        Err(error) => Err(FunctionError::EditorThemeError(error)),
+    }
+}
+
+pub(super) fn help(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    let functions = FUNCTIONS.lock().expect("Could not resolve FUNCTIONS");
+    let mut output = EDITOR_OUTPUT.lock().expect("Failed to get editor output");
+    if arguments.is_empty() {
+        for function in functions.iter() {
+            output.push(function.to_signature_string());
+        }
+        return Ok(InterpreterResult::Nil);
+    }
+    expected_arguments_count(arguments, 1)?;
+    let name = string_argument(arguments, 0)?;
+    match functions.iter().find(|function| function.name == name) {
+        Some(function) => {
+            output.push(function.to_signature_string());
+            if !function.doc.is_empty() {
+                output.push(function.doc.clone());
+            }
+            Ok(InterpreterResult::Nil)
+        }
+        None => Err(FunctionError::UnknownFunction(name)),
+    }
+}
+
+pub(super) fn debug(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    set_debug_mode(bool_argument(arguments, 0)?);
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn breakpoint(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    let breakpoint = match &arguments[0] {
+        InterpreterResult::Str(function) => Breakpoint::Function(function.clone()),
+        InterpreterResult::Num(line) if *line >= 1.0 => Breakpoint::Line(*line as u32 - 1),
+        _ => return Err(FunctionError::InvalidBreakpoint),
+    };
+    Ok(InterpreterResult::Bool(toggle_breakpoint(breakpoint)))
+}
+
+pub(super) fn clear_all_breakpoints(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    clear_breakpoints();
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn profile(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    set_profiling(bool_argument(arguments, 0)?);
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn profile_report_table(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    let report = profile_report();
+    EDITOR_OUTPUT.lock().expect("Failed to get editor output").extend(report);
+    Ok(InterpreterResult::Nil)
+}
+
+pub(super) fn profile_export(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    let path = string_argument(arguments, 0)?;
+    match export_folded_stacks(&path) {
+        Ok(_) => Ok(InterpreterResult::Nil),
+        Err(error) => Err(FunctionError::ProfileExportError(error)),
+    }
+}
+
+pub(super) fn record(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 1)?;
+    let name = string_argument(arguments, 0)?;
+    let is_native = FUNCTIONS
+        .lock()
+        .expect("Could not resolve FUNCTIONS")
+        .iter()
+        .any(|function| function.name == name && matches!(function.instructions, InstructionsDef::NativeFunction(_)));
+    if is_native || name.is_empty() || !name.chars().all(char::is_alphanumeric) {
+        return Err(FunctionError::ReservedFunctionName(name));
+    }
+    match start_recording(name) {
+        Ok(_) => Ok(InterpreterResult::Nil),
+        Err(error) => Err(FunctionError::RecordingError(error)),
+    }
+}
+
+pub(super) fn stop(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_empty_arguments(arguments)?;
+    match stop_recording() {
+        Ok(signature) => {
+            EDITOR_OUTPUT.lock().expect("Failed to get editor output").push(format!("Recorded {}", signature));
+            Ok(InterpreterResult::Nil)
+        }
+        Err(error) => Err(FunctionError::RecordingError(error)),
+    }
+}
+
+pub(super) fn limits(arguments: &Vec<InterpreterResult>) -> Result<InterpreterResult, FunctionError> {
+    expected_arguments_count(arguments, 3)?;
+    let max_steps = number_argument(arguments, 0)?.max(1.0) as usize;
+    let max_call_depth = number_argument(arguments, 1)?.clamp(1.0, MAX_CALL_DEPTH as f64) as usize;
+    let max_allocations = number_argument(arguments, 2)?.max(1.0) as usize;
+    set_limits(max_steps, max_call_depth, max_allocations);
+    Ok(InterpreterResult::Nil)
+}
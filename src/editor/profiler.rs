@@ -0,0 +1,157 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fs,
+    io,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::debugger::TOP_LEVEL_FUNCTION;
+
+pub static PROFILER_STATE: Mutex<ProfilerState> = Mutex::new(ProfilerState {
+    enabled: false,
+    profiles: BTreeMap::new(),
+    folded_stacks: BTreeMap::new(),
+    stack: vec![],
+});
+
+pub struct ProfilerState {
+    pub enabled: bool,
+    profiles: BTreeMap<String, FunctionProfile>,
+    /// Exclusive time spent in each call path, keyed by `caller;callee` names.
+    folded_stacks: BTreeMap<String, Duration>,
+    stack: Vec<ProfileFrame>,
+}
+
+#[derive(Default, Clone)]
+pub struct FunctionProfile {
+    pub calls: u32,
+    pub inclusive_time: Duration,
+    pub exclusive_time: Duration,
+    pub actions: GameActions,
+}
+
+/// Game actions triggered by a function, including the ones of the functions it calls.
+#[derive(Default, Clone, Copy)]
+pub struct GameActions {
+    pub moves: u32,
+    pub breaks: u32,
+    pub loots: u32,
+}
+
+pub enum GameAction {
+    Move,
+    Break,
+    Loot,
+}
+
+struct ProfileFrame {
+    function: String,
+    start: Instant,
+    children_time: Duration,
+    actions: GameActions,
+}
+
+/// Enables profiling and clears the previous records, or disables it keeping the records.
+/// Only calls both starting and ending while profiling are recorded, so calls still running when
+/// profiling is toggled, like the one toggling it, are left out.
+pub fn set_profiling(enabled: bool) {
+    let mut profiler_state = PROFILER_STATE.lock().expect("Failed to get profiler state");
+    if enabled {
+        profiler_state.profiles.clear();
+        profiler_state.folded_stacks.clear();
+    }
+    profiler_state.stack.clear();
+    profiler_state.enabled = enabled;
+}
+
+pub fn enter_function(function: &str) {
+    let mut profiler_state = PROFILER_STATE.lock().expect("Failed to get profiler state");
+    if !profiler_state.enabled {
+        return;
+    }
+    profiler_state.stack.push(ProfileFrame {
+        function: function.to_string(),
+        start: Instant::now(),
+        children_time: Duration::ZERO,
+        actions: GameActions::default(),
+    });
+}
+
+pub fn exit_function() {
+    let mut profiler_state = PROFILER_STATE.lock().expect("Failed to get profiler state");
+    if !profiler_state.enabled {
+        return;
+    }
+    let Some(frame) = profiler_state.stack.pop() else {
+        return;
+    };
+    let inclusive_time = frame.start.elapsed();
+    let exclusive_time = inclusive_time.saturating_sub(frame.children_time);
+    if let Some(parent) = profiler_state.stack.last_mut() {
+        parent.children_time += inclusive_time;
+    }
+    let path = profiler_state
+        .stack
+        .iter()
+        .map(|parent| parent.function.as_str())
+        .chain([frame.function.as_str()])
+        .fold(TOP_LEVEL_FUNCTION.to_string(), |path, function| path + ";" + function);
+    *profiler_state.folded_stacks.entry(path).or_default() += exclusive_time;
+    let profile = profiler_state.profiles.entry(frame.function).or_default();
+    profile.calls += 1;
+    profile.inclusive_time += inclusive_time;
+    profile.exclusive_time += exclusive_time;
+    profile.actions.moves += frame.actions.moves;
+    profile.actions.breaks += frame.actions.breaks;
+    profile.actions.loots += frame.actions.loots;
+}
+
+/// Counts the action for every function being profiled.
+pub fn record_game_action(action: GameAction) {
+    let mut profiler_state = PROFILER_STATE.lock().expect("Failed to get profiler state");
+    for frame in profiler_state.stack.iter_mut() {
+        match action {
+            GameAction::Move => frame.actions.moves += 1,
+            GameAction::Break => frame.actions.breaks += 1,
+            GameAction::Loot => frame.actions.loots += 1,
+        }
+    }
+}
+
+/// Lines of the profile table, functions sorted by inclusive time.
+pub fn profile_report() -> Vec<String> {
+    let profiler_state = PROFILER_STATE.lock().expect("Failed to get profiler state");
+    let mut profiles: Vec<(&String, &FunctionProfile)> = profiler_state.profiles.iter().collect();
+    profiles.sort_by_key(|(_, profile)| Reverse(profile.inclusive_time));
+    let mut report = vec![format!(
+        "{:<16} {:>6} {:>10} {:>10} {:>6} {:>6} {:>6}",
+        "function", "calls", "incl ms", "excl ms", "moves", "breaks", "loots"
+    )];
+    for (function, profile) in profiles {
+        report.push(format!(
+            "{:<16} {:>6} {:>10.3} {:>10.3} {:>6} {:>6} {:>6}",
+            function,
+            profile.calls,
+            profile.inclusive_time.as_secs_f64() * 1000.0,
+            profile.exclusive_time.as_secs_f64() * 1000.0,
+            profile.actions.moves,
+            profile.actions.breaks,
+            profile.actions.loots,
+        ));
+    }
+    report
+}
+
+/// Writes the call paths in the folded stack format read by flamegraph tools,
+/// one `caller;callee microseconds` line per path.
+pub fn export_folded_stacks(path: &str) -> io::Result<()> {
+    let profiler_state = PROFILER_STATE.lock().expect("Failed to get profiler state");
+    let content: String = profiler_state
+        .folded_stacks
+        .iter()
+        .map(|(stack, time)| format!("{} {}\n", stack, time.as_micros()))
+        .collect();
+    fs::write(path, content)
+}
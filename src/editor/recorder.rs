@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+
+use super::{
+    functions::{register_function, FunctionDef},
+    grammar::{Expression, Function, FunctionDefinition, Operation, Primary, Unary},
+};
+
+pub static RECORDER_STATE: Mutex<Option<Recording>> = Mutex::new(None);
+
+/// Statements run since `record("name")` was called.
+pub struct Recording {
+    pub name: String,
+    pub body: Vec<Expression>,
+    /// Line of each statement, one line per prompt.
+    pub lines: Vec<u32>,
+    /// Source of the statements recorded from each prompt.
+    pub prompts: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    AlreadyRecording,
+    NotRecording,
+    NothingRecorded,
+}
+
+pub fn start_recording(name: String) -> Result<(), RecordError> {
+    let mut recorder_state = RECORDER_STATE.lock().expect("Failed to get recorder state");
+    if recorder_state.is_some() {
+        return Err(RecordError::AlreadyRecording);
+    }
+    *recorder_state = Some(Recording { name, body: vec![], lines: vec![], prompts: vec![] });
+    Ok(())
+}
+
+pub fn is_recording() -> bool {
+    RECORDER_STATE.lock().expect("Failed to get recorder state").is_some()
+}
+
+/// Adds a statement that ran without error to the recording in progress, starting a new line
+/// for the first statement of a prompt. Returns whether the statement was recorded.
+pub fn capture_statement(statement: &Expression, source: &str, new_prompt: bool) -> bool {
+    let mut recorder_state = RECORDER_STATE.lock().expect("Failed to get recorder state");
+    let Some(recording) = recorder_state.as_mut() else {
+        return false;
+    };
+    if is_eof(statement) {
+        return false;
+    }
+    match recording.prompts.last_mut() {
+        Some(prompt) if !new_prompt => {
+            prompt.push(' ');
+            prompt.push_str(source);
+        }
+        _ => recording.prompts.push(source.to_string()),
+    }
+    recording.body.push(statement.clone());
+    recording.lines.push(recording.prompts.len() as u32 - 1);
+    true
+}
+
+/// Ends the recording and registers a function running every captured statement in order.
+/// Returns the signature of the function.
+pub fn stop_recording() -> Result<String, RecordError> {
+    let recording = RECORDER_STATE
+        .lock()
+        .expect("Failed to get recorder state")
+        .take()
+        .ok_or(RecordError::NotRecording)?;
+    if recording.body.is_empty() {
+        return Err(RecordError::NothingRecorded);
+    }
+    let definition = FunctionDefinition {
+        name: recording.name,
+        parameters: vec![],
+        body: recording.body,
+        lines: recording.lines,
+        starts: vec![],
+        end: 0,
+        doc: format!("Recorded from : {}", recording.prompts.join(" ; ")),
+    };
+    // Recorded lines are prompt indices, so each prompt stands for one line of the source.
+    let function = FunctionDef::from_definition(&definition, Arc::from(recording.prompts));
+    let signature = function.to_signature_string();
+    register_function(function);
+    Ok(signature)
+}
+
+fn is_eof(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Function(Function::Operation(Operation::Unary(Unary::Primary(Primary::Eof))))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{capture_statement, start_recording, stop_recording, RecordError};
+    use crate::editor::{
+        functions::{InstructionsDef, FUNCTIONS},
+        grammar::{resolve_ast, Expression},
+        tokenizer::get_prompt_tokens,
+    };
+
+    fn statements(source: &str) -> Vec<Expression> {
+        let tokens = get_prompt_tokens(source.to_string()).expect("Prompt should tokenize");
+        resolve_ast(tokens).expect("Prompt should parse").tree
+    }
+
+    #[test]
+    fn records_statements_one_line_per_prompt() {
+        let first_prompt = statements("moveUp() loot()");
+        let second_prompt = statements("zoomIn()");
+        assert!(!capture_statement(&first_prompt[0], "moveUp()", true));
+        start_recording("recordedWalk".to_string()).expect("Recording should start");
+        assert!(matches!(start_recording("other".to_string()), Err(RecordError::AlreadyRecording)));
+        assert!(capture_statement(&first_prompt[0], "moveUp()", true));
+        assert!(capture_statement(&first_prompt[1], "loot()", false));
+        // End of the prompt
+        assert!(!capture_statement(&first_prompt[2], "", false));
+        assert!(capture_statement(&second_prompt[0], "zoomIn()", true));
+        assert_eq!(stop_recording().expect("Recording should stop"), "recordedWalk() -> Any");
+        assert!(matches!(stop_recording(), Err(RecordError::NotRecording)));
+
+        let functions = FUNCTIONS.lock().expect("Could not resolve FUNCTIONS");
+        let function = functions.iter().find(|function| function.name == "recordedWalk").expect("Function should exist");
+        assert_eq!(function.doc, "Recorded from : moveUp() loot() ; zoomIn()");
+        match &function.instructions {
+            InstructionsDef::Expressions(body, lines, source) => {
+                assert_eq!(body, &vec![first_prompt[0].clone(), first_prompt[1].clone(), second_prompt[0].clone()]);
+                assert_eq!(lines, &vec![0, 0, 1]);
+                assert_eq!(source.as_ref(), ["moveUp() loot()", "zoomIn()"]);
+            }
+            InstructionsDef::NativeFunction(_) => panic!("Recorded function should not be native"),
+        }
+    }
+}
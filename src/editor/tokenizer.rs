@@ -1,4 +1,4 @@
-use std::{any::Any, char, collections::HashMap, str::Chars};
+use std::{char, collections::HashMap, str::Chars};
 
 use lazy_static::lazy_static;
 
@@ -8,6 +8,8 @@ pub struct Token {
     pub lexeme: String,
     pub literal: Option<Literal>,
     pub line: u32,
+    pub start: usize,
+    pub end: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -16,6 +18,7 @@ pub enum Literal {
     Num(f64),
     Identifier(TokenType),
     Label(String),
+    Comment(String),
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -35,6 +38,9 @@ pub enum TokenType{
 
     // REFENCES
     LABEL,
+
+    // Comments, ignored by the grammar except as function documentation.
+    COMMENT,
 }
 
 lazy_static! {
@@ -65,14 +71,32 @@ pub enum TokenizerError{
     IdentifierMissmatch,
     InvalidFunctionSyntax,
     NoIdentifierNorFunctionError,
+    UnterminatedComment,
 }
 
 pub fn get_prompt_tokens(prompt: String) -> Result<Vec<Token>, TokenizerError> {
-    let line = 0;
+    let (mut tokens, error) = scan_prompt_tokens(&prompt);
+    if let Some((_, error)) = error {
+        return Err(error);
+    }
+    let end = prompt.len();
+    tokens.push(Token{ token_type: TokenType::EOF, lexeme: "".to_string(), literal: None, line: prompt.matches('\n').count() as u32, start: end, end });
+    Ok(tokens)
+}
+
+/// Scans as many tokens as possible, returning them along with the byte offset and reason
+/// of the first scan failure, if any. No EOF token is added.
+pub fn scan_prompt_tokens(prompt: &str) -> (Vec<Token>, Option<(usize, TokenizerError)>) {
+    let mut line = 0;
     let mut tokens = vec![];
     let mut characters = prompt.chars();
 
-    while let Some(character) = characters.next() {
+    loop {
+        let start = prompt.len() - characters.as_str().len();
+        let Some(character) = characters.next() else {
+            break;
+        };
+        let token_count = tokens.len();
         match character {
             '(' => add_token(TokenType::LEFT_PAREN, character, line, &mut tokens),
             ')' => add_token(TokenType::RIGHT_PAREN, character, line, &mut tokens),
@@ -84,43 +108,88 @@ pub fn get_prompt_tokens(prompt: String) -> Result<Vec<Token>, TokenizerError> {
             '+' => add_token(TokenType::PLUS, character, line, &mut tokens),
             ';' => add_token(TokenType::SEMICOLON, character, line, &mut tokens),
             '*' => add_token(TokenType::STAR, character, line, &mut tokens),
-            '/' => add_token(TokenType::SLASH, character, line, &mut tokens),
+            '/' => match characters.clone().next() {
+                Some('/') | Some('*') => match resolve_comment(&mut characters) {
+                    Ok(comment) => {
+                        add_token_with_literal(TokenType::COMMENT, character, line, Literal::Comment(comment.clone()), &mut tokens);
+                        line += comment.matches('\n').count() as u32;
+                    }
+                    Err(err) => return (tokens, Some((start, err))),
+                },
+                _ => add_token(TokenType::SLASH, character, line, &mut tokens),
+            },
             '!' => add_token(resolve_two_chars_type(TokenType::BANG, &mut characters), character, line, &mut tokens),
             '=' => add_token(resolve_two_chars_type(TokenType::EQUAL, &mut characters), character, line, &mut tokens),
             '<' => add_token(resolve_two_chars_type(TokenType::LESS, &mut characters), character, line, &mut tokens),
             '>' => add_token(resolve_two_chars_type(TokenType::GREATER, &mut characters), character, line, &mut tokens),
             '"' => {
-                match resolve_string(character, &mut characters) {
+                match resolve_string(&mut characters) {
                     Ok(value) => add_token_with_literal(TokenType::STRING, character, line, Literal::Str(value), &mut tokens),
-                    Err(err) => return Err(err),
+                    Err(err) => return (tokens, Some((start, err))),
                 }
             },
-            ' ' => continue,
+            ' ' | '\t' | '\r' => continue,
+            '\n' => {
+                line += 1;
+                continue;
+            }
             _ => {
                 if character.is_ascii_digit() {
                     match resolve_number(character, &mut characters) {
                         Ok(value) => add_token_with_literal(TokenType::NUMBER, character, line, Literal::Num(value), &mut tokens),
-                        Err(err) => return Err(err),
+                        Err(err) => return (tokens, Some((start, err))),
                     }
-                    continue;
                 } else if character.is_alphanumeric() {
                     match resolve_identifier(character, &mut characters) {
                         Ok(value) => add_token_with_literal(TokenType::IDENTIFIER, character, line, Literal::Identifier(value), &mut tokens),
                         Err(_) => match resolve_label(character, &mut characters) {
-                            Ok(label) => tokens.push(Token { token_type: TokenType::LABEL, lexeme: "label".to_string(), literal: Some(label), line }),
-                            Err(_) => return Err(TokenizerError::NoIdentifierNorFunctionError),
+                            Ok(label) => tokens.push(Token { token_type: TokenType::LABEL, lexeme: "label".to_string(), literal: Some(label), line, start, end: start }),
+                            Err(_) => return (tokens, Some((start, TokenizerError::NoIdentifierNorFunctionError))),
                         },
                     }
                 } else {
-                    return Err(TokenizerError::TokenScanError);
+                    return (tokens, Some((start, TokenizerError::TokenScanError)));
                 }
             }
         }
+        if tokens.len() > token_count {
+            let end = prompt.len() - characters.as_str().len();
+            if let Some(token) = tokens.last_mut() {
+                token.start = start;
+                token.end = end;
+            }
+        }
     }
 
-    tokens.push(Token{ token_type: TokenType::EOF, lexeme: "".to_string(), literal: None, line });
+    (tokens, None)
+}
 
-    Ok(tokens)
+/// Resolves `// comment` up to the end of the line, or `/* comment */` which may span lines.
+/// The leading slash has already been consumed.
+fn resolve_comment(characters: &mut Chars) -> Result<String, TokenizerError> {
+    let mut result = String::new();
+    match characters.next() {
+        Some('/') => {
+            for character in characters.clone() {
+                if character == '\n' {
+                    break;
+                }
+                result.push(character);
+                characters.next();
+            }
+            Ok(result)
+        }
+        _ => {
+            while let Some(character) = characters.next() {
+                if character == '*' && characters.clone().next() == Some('/') {
+                    characters.next();
+                    return Ok(result);
+                }
+                result.push(character);
+            }
+            Err(TokenizerError::UnterminatedComment)
+        }
+    }
 }
 
 fn resolve_label(first_character: char, characters: &mut Chars<'_>) -> Result<Literal, TokenizerError> {
@@ -179,9 +248,8 @@ fn resolve_number(first_value: char, characters: &mut Chars) -> Result<f64, Toke
     Ok(result.parse::<f64>().expect("Error while parsing token from String to f64"))
 }
 
-fn resolve_string(first_value: char, characters: &mut Chars) -> Result<String, TokenizerError> {
+fn resolve_string(characters: &mut Chars) -> Result<String, TokenizerError> {
     let mut result = String::new();
-    result.push(first_value);
     for character in characters.by_ref() {
         if character != '"' {
             result.push(character);
@@ -216,10 +284,72 @@ fn resolve_two_chars_type(token_type: TokenType, characters: &mut Chars) -> Toke
 }
 
 fn add_token(token_type: TokenType, character: char, line: u32, tokens: &mut Vec<Token>) {
-    tokens.push(Token { token_type, lexeme: character.to_string(), literal: None, line });
+    tokens.push(Token { token_type, lexeme: character.to_string(), literal: None, line, start: 0, end: 0 });
 }
 
 fn add_token_with_literal(token_type: TokenType, character: char, line: u32, literal: Literal, tokens: &mut Vec<Token>) {
-    tokens.push(Token { token_type, lexeme: character.to_string(), literal: Some(literal), line });
+    tokens.push(Token { token_type, lexeme: character.to_string(), literal: Some(literal), line, start: 0, end: 0 });
+}
+
+
+/// Word being typed at the end of the input, used to complete function names.
+pub fn get_current_token(input: &[char]) -> Option<String> {
+    let token_start = get_current_token_start(input);
+    if token_start == input.len() {
+        return None;
+    }
+    Some(input[token_start..].iter().collect())
 }
 
+pub fn get_current_token_start(input: &[char]) -> usize {
+    let mut token_start = input.len();
+    while token_start > 0 && input[token_start - 1].is_alphanumeric() {
+        token_start -= 1;
+    }
+    token_start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_prompt_tokens, scan_prompt_tokens, Literal, TokenType, TokenizerError};
+
+    #[test]
+    fn string_literals_exclude_quotes() {
+        let tokens = get_prompt_tokens("help(\"moveUp\")".to_string()).expect("Prompt should tokenize");
+        match &tokens[2].literal {
+            Some(Literal::Str(value)) => assert_eq!(value, "moveUp"),
+            literal => panic!("Expected a string literal, got {:?}", literal),
+        }
+        assert_eq!((tokens[2].start, tokens[2].end), (5, 13));
+    }
+
+    #[test]
+    fn scans_token_offsets_and_lines() {
+        let (tokens, error) = scan_prompt_tokens("moveUp()\n  1 >= 2 // done");
+        assert!(error.is_none());
+        let scanned: Vec<(TokenType, usize, usize, u32)> =
+            tokens.iter().map(|token| (token.token_type, token.start, token.end, token.line)).collect();
+        assert_eq!(
+            scanned,
+            vec![
+                (TokenType::LABEL, 0, 6, 0),
+                (TokenType::LEFT_PAREN, 6, 7, 0),
+                (TokenType::RIGHT_PAREN, 7, 8, 0),
+                (TokenType::NUMBER, 11, 12, 1),
+                (TokenType::GREATER_EQUAL, 13, 15, 1),
+                (TokenType::NUMBER, 16, 17, 1),
+                (TokenType::COMMENT, 18, 25, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_the_first_scan_error() {
+        let (tokens, error) = scan_prompt_tokens("moveUp() \"open");
+        assert_eq!(tokens.len(), 3);
+        assert!(matches!(error, Some((9, TokenizerError::StringTokenScanError))));
+        let (_, error) = scan_prompt_tokens("loot() # /* never closed");
+        assert!(matches!(error, Some((7, TokenizerError::TokenScanError))));
+        assert!(get_prompt_tokens("/* never closed".to_string()).is_err());
+    }
+}
@@ -1,52 +1,106 @@
-use noise::{core::perlin::perlin_2d, permutationtable::PermutationTable, utils::*};
-
 use std::{
+    collections::VecDeque,
+    fmt::Display,
     ops::{Deref, DerefMut},
     sync::Mutex,
     time::SystemTime,
 };
 
-use rand::prelude::Rng;
+use rand::{rngs::StdRng, SeedableRng};
 use raylib::ffi::Vector2;
 
 use crate::{
-    item::{InventoryItem, Item, MapItem, TreeItem},
-    GAME_HEIGHT, GAME_WIDTH,
+    clock::Clock,
+    creature::Creature,
+    editor::{completion::Completion, history::HistorySearch},
+    item::{recipe::RECIPES, registry::ITEMS, InventoryItem, MapItem, Tool, HAND},
+    world::World,
 };
 
-pub static EDITOR_STATE: Mutex<EditorState> = Mutex::new(EditorState {
-    buffer: vec![],
-    commands: vec![],
-    input_history: vec![],
-});
+pub static EDITOR_STATE: Mutex<EditorState> = Mutex::new(EditorState::empty());
+
+/// Lines printed by functions while a prompt is processed, flushed into the editor history.
+pub static EDITOR_OUTPUT: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+/// Queues an error line for the editor history.
+pub fn push_editor_error(message: &dyn Display) {
+    EDITOR_OUTPUT.lock().expect("Failed to get editor output").push(format!("ERR-{}", message));
+}
 
 pub struct EditorState {
     pub buffer: Vec<char>,
+    pub cursor: usize,
     pub commands: Vec<String>,
     pub input_history: Vec<String>,
+    pub completion: Option<Completion>,
+    pub history_index: Option<usize>,
+    pub history_draft: Vec<char>,
+    pub history_search: Option<HistorySearch>,
+}
+
+impl EditorState {
+    /// Editor with an empty prompt and history.
+    pub const fn empty() -> EditorState {
+        EditorState {
+            buffer: vec![],
+            cursor: 0,
+            commands: vec![],
+            input_history: vec![],
+            completion: None,
+            history_index: None,
+            history_draft: vec![],
+            history_search: None,
+        }
+    }
 }
 
-pub static MAP_STATE: Mutex<MapState> = Mutex::new(MapState {
-    tiles: vec![],
-    player: Player {
-        velocity: 0.25,
-        position: Vector2 { x: 0.0, y: 0.0 },
-        previous_position: Vector2 { x: 0.0, y: 0.0 },
-        animation_state: DEFAULT_ANIMATION,
-        light_vision: 7.0,
-        inventory: vec![],
-    },
-    zoom: 1.4,
-    items: vec![],
-    is_inventory_toggled: false,
-});
+pub static MAP_STATE: Mutex<MapState> = Mutex::new(MapState::empty());
+
+pub const MAX_HEALTH: f32 = 100.0;
+const LAVA_DAMAGE_PER_SECOND: f32 = 25.0;
+/// Seconds without damage before health regenerates.
+const REGENERATION_DELAY: f32 = 5.0;
+const REGENERATION_PER_SECOND: f32 = 2.0;
+/// Seconds the death screen is shown before respawning.
+pub const RESPAWN_DELAY: f32 = 3.0;
+
+const PLAYER_ATTACK_DAMAGE: f32 = 10.0;
+/// Damage added by each tier of the equipped tool.
+const PLAYER_ATTACK_DAMAGE_PER_TIER: f32 = 5.0;
+/// Seconds between two attacks of the player.
+const PLAYER_ATTACK_DELAY: f32 = 0.5;
+
+pub const MAX_HUNGER: f32 = 100.0;
+pub const MAX_THIRST: f32 = 100.0;
+const HUNGER_PER_SECOND: f32 = 0.2;
+const THIRST_PER_SECOND: f32 = 0.3;
+/// Thirst restored by one drink.
+const DRINK_AMOUNT: f32 = 30.0;
+/// Damage per second taken while starving or dehydrated.
+const DEPRIVATION_DAMAGE_PER_SECOND: f32 = 1.0;
+/// Hunger or thirst below which the player slows down and sees less far.
+const LOW_METER: f32 = 25.0;
+const BASE_VELOCITY: f32 = 0.25;
+/// Kept a divisor of one so that the displayed position still lands on tiles.
+const WEAK_VELOCITY: f32 = 0.125;
+const BASE_LIGHT_VISION: f32 = 7.0;
+const WEAK_LIGHT_VISION: f32 = 4.0;
 
 pub struct MapState {
-    pub tiles: Vec<Vec<Tile>>,
+    pub world: World,
     pub player: Player,
     pub zoom: f32,
     pub items: Vec<MapItem>,
     pub is_inventory_toggled: bool,
+    /// Where the player respawns after dying.
+    pub spawn_point: Vector2,
+    pub clock: Clock,
+    pub creatures: Vec<Creature>,
+    pub creature_spawn_cooldown: f32,
+    /// Drives creature spawns and wandering, seeded from the world so that runs are reproducible.
+    pub creature_rng: Option<StdRng>,
+    /// Moves queued by `goTo`, made one at a time once the player is ready.
+    pub path: VecDeque<Direction>,
 }
 
 pub struct Player {
@@ -56,10 +110,47 @@ pub struct Player {
     pub animation_state: AnimationState,
     pub light_vision: f32,
     pub inventory: Vec<InventoryItem>,
+    /// Id of the inventory item used to mine.
+    pub equipped_tool: Option<String>,
+    pub health: f32,
+    pub hunger: f32,
+    pub thirst: f32,
+    /// Seconds since the player was last damaged, to delay regeneration.
+    pub since_damage: f32,
+    /// Seconds left before respawning, only set while the player is dead.
+    pub respawn_cooldown: Option<f32>,
+    /// Seconds left before the player can attack again.
+    pub attack_cooldown: f32,
 }
 
 impl Player {
+    /// Player at the origin with an empty inventory, placed on the spawn by `init_map`.
+    pub const fn empty() -> Player {
+        Player {
+            velocity: BASE_VELOCITY,
+            position: Vector2 { x: 0.0, y: 0.0 },
+            previous_position: Vector2 { x: 0.0, y: 0.0 },
+            animation_state: DEFAULT_ANIMATION,
+            light_vision: BASE_LIGHT_VISION,
+            inventory: vec![],
+            equipped_tool: None,
+            health: MAX_HEALTH,
+            hunger: MAX_HUNGER,
+            thirst: MAX_THIRST,
+            since_damage: 0.0,
+            respawn_cooldown: None,
+            attack_cooldown: 0.0,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.respawn_cooldown.is_some()
+    }
+
     pub fn is_ready(&self) -> bool {
+        if self.is_dead() {
+            return false;
+        }
         match self.animation_state.cooldown {
             Some(cooldown) => {
                 if cooldown > 0.0 {
@@ -71,14 +162,101 @@ impl Player {
         }
     }
 
-    pub fn add_item_in_inventory(&mut self, inventory_item: InventoryItem) {
+    /// Adds one item to the first stack of the same item that isn't full, or to a new stack.
+    pub fn add_item_in_inventory(&mut self, id: &str) {
+        let max_stack = ITEMS.get(id).map_or(1, |definition| definition.max_stack);
         for item in self.inventory.iter_mut() {
-            if item.item.get_name() == inventory_item.item.get_name() {
+            if item.id == id && item.number < max_stack {
                 item.number += 1;
                 return;
             }
         }
-        self.inventory.push(inventory_item);
+        self.inventory.push(InventoryItem { number: 1, id: id.to_string() });
+    }
+
+    pub fn count_item(&self, id: &str) -> i32 {
+        self.inventory
+            .iter()
+            .filter(|item| item.id == id)
+            .map(|item| item.number)
+            .sum()
+    }
+
+    /// Removes items from the last stacks first, dropping the emptied stacks.
+    pub fn remove_item_from_inventory(&mut self, id: &str, mut count: i32) {
+        for item in self.inventory.iter_mut().rev().filter(|item| item.id == id) {
+            let removed = count.min(item.number);
+            item.number -= removed;
+            count -= removed;
+        }
+        self.inventory.retain(|item| item.number > 0);
+    }
+
+    /// Number of times the recipe of the item can be crafted with the inventory.
+    pub fn craftable_times(&self, id: &str) -> Result<i32, CraftError> {
+        let recipe = RECIPES.get(id).ok_or_else(|| CraftError::UnknownRecipe(id.to_string()))?;
+        Ok(recipe
+            .inputs
+            .iter()
+            .map(|(input, count)| self.count_item(input) / count)
+            .min()
+            .unwrap_or(0))
+    }
+
+    /// Consumes the inputs of the recipe of the item and adds its output, `times` times.
+    pub fn craft(&mut self, id: &str, times: i32) -> Result<(), CraftError> {
+        if times < 1 {
+            return Err(CraftError::InvalidTimes(times));
+        }
+        if self.craftable_times(id)? < times {
+            return Err(CraftError::MissingItems);
+        }
+        let recipe = RECIPES.get(id).expect("BUG - recipe has been checked");
+        for (input, count) in recipe.inputs.iter() {
+            self.remove_item_from_inventory(input, count * times);
+        }
+        for _ in 0..recipe.count * times {
+            self.add_item_in_inventory(&recipe.output);
+        }
+        Ok(())
+    }
+
+    /// Eats one food item of the inventory to restore hunger.
+    pub fn eat(&mut self, id: &str) -> Result<(), EatError> {
+        if self.count_item(id) == 0 {
+            return Err(EatError::NotInInventory);
+        }
+        let nutrition = ITEMS
+            .get(id)
+            .and_then(|definition| definition.nutrition)
+            .ok_or(EatError::NotFood)?;
+        if !self.is_ready() {
+            return Err(EatError::PlayerBusy);
+        }
+        self.remove_item_from_inventory(id, 1);
+        self.hunger = (self.hunger + nutrition).min(MAX_HUNGER);
+        Ok(())
+    }
+
+    /// Tool of the equipped item, or the hand when nothing usable is equipped.
+    pub fn get_tool(&self) -> Tool {
+        self.equipped_tool
+            .as_ref()
+            .filter(|id| self.inventory.iter().any(|item| &item.id == *id))
+            .and_then(|id| ITEMS.get(id))
+            .and_then(|definition| definition.tool)
+            .unwrap_or(HAND)
+    }
+
+    pub fn equip(&mut self, id: &str) -> Result<(), EquipError> {
+        if !self.inventory.iter().any(|item| item.id == id) {
+            return Err(EquipError::NotInInventory);
+        }
+        if ITEMS.get(id).and_then(|definition| definition.tool).is_none() {
+            return Err(EquipError::NotATool);
+        }
+        self.equipped_tool = Some(id.to_string());
+        Ok(())
     }
 }
 
@@ -115,18 +293,70 @@ pub enum Status {
 #[derive(Debug)]
 pub enum MoveError {
     HitWall,
-    NoTiles,
     PlayerBusy,
 }
 
 #[derive(Debug)]
 pub enum BreakError {
-    Nothing,
     Unbreakable,
     PlayerBusy,
+    /// The tile requires a tool of this tier.
+    ToolTierTooLow(u8),
+}
+
+#[derive(Debug)]
+pub enum EquipError {
+    NotInInventory,
+    NotATool,
+}
+
+#[derive(Debug)]
+pub enum PlaceError {
+    NotInInventory,
+    NotPlaceable,
+    /// The item places nothing on the target tile, as listed in its definition.
+    NotPlaceableHere,
+    /// An item or a creature lies on the target tile.
+    Occupied,
+    PlayerBusy,
+}
+
+#[derive(Debug)]
+pub enum EatError {
+    NotInInventory,
+    NotFood,
+    PlayerBusy,
+}
+
+#[derive(Debug)]
+pub enum DrinkError {
+    NoWaterNearby,
+    PlayerBusy,
+}
+
+#[derive(Debug)]
+pub enum AttackError {
+    NothingToAttack,
+    PlayerBusy,
 }
 
 #[derive(Debug)]
+pub enum CraftError {
+    UnknownRecipe(String),
+    InvalidTimes(i32),
+    MissingItems,
+}
+
+/// How a solid tile is mined.
+pub struct Mining {
+    /// Seconds to break the tile with a tool of speed 1.
+    pub hardness: f32,
+    pub required_tier: u8,
+    /// Ids of the items dropped once broken.
+    pub drops: &'static [&'static str],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Up,
     Down,
@@ -134,61 +364,69 @@ pub enum Direction {
     Right,
 }
 
+impl Direction {
+    /// Direction named in scripts : up, down, left or right.
+    pub fn from_name(name: &str) -> Option<Direction> {
+        match name {
+            "up" => Some(Direction::Up),
+            "down" => Some(Direction::Down),
+            "left" => Some(Direction::Left),
+            "right" => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+
+    /// Position next to the given one in this direction.
+    pub fn next_to(&self, position: Vector2) -> Vector2 {
+        match self {
+            Direction::Up => Vector2 { x: position.x, y: position.y - 1.0 },
+            Direction::Down => Vector2 { x: position.x, y: position.y + 1.0 },
+            Direction::Left => Vector2 { x: position.x - 1.0, y: position.y },
+            Direction::Right => Vector2 { x: position.x + 1.0, y: position.y },
+        }
+    }
+}
+
 impl MapState {
+    /// Map without world nor creatures, filled by `init_map`.
+    pub const fn empty() -> MapState {
+        MapState {
+            world: World::empty(),
+            player: Player::empty(),
+            zoom: 1.4,
+            items: vec![],
+            is_inventory_toggled: false,
+            spawn_point: Vector2 { x: 0.0, y: 0.0 },
+            clock: Clock::start(),
+            creatures: vec![],
+            creature_spawn_cooldown: 0.0,
+            creature_rng: None,
+            path: VecDeque::new(),
+        }
+    }
+
     pub fn may_move_player(&mut self, direction: Direction) -> Result<(), MoveError> {
         if !self.player.is_ready() {
             return Err(MoveError::PlayerBusy);
         }
-        let current_player_position = self.player.position;
-        let (current_x, current_y) = (
-            current_player_position.x as usize,
-            current_player_position.y as usize,
-        );
-        let mut next_x = 0;
-        let mut next_y = 0;
-        match direction {
-            Direction::Up => {
-                if current_y == 0 {
-                    return Err(MoveError::NoTiles);
-                }
-                next_x = current_x;
-                next_y = current_y - 1;
-            }
-            Direction::Down => {
-                if current_y == GAME_HEIGHT as usize - 1 {
-                    return Err(MoveError::NoTiles);
-                }
-                next_x = current_x;
-                next_y = current_y + 1;
-            }
-            Direction::Left => {
-                if current_x == 0 {
-                    return Err(MoveError::NoTiles);
-                }
-                next_x = current_x - 1;
-                next_y = current_y;
-            }
-            Direction::Right => {
-                if current_x == GAME_WIDTH as usize - 1 {
-                    return Err(MoveError::NoTiles);
-                }
-                next_x = current_x + 1;
-                next_y = current_y;
-            }
-        };
-        let next_tile = if let Some(line) = self.tiles.get(next_y) {
-            if let Some(next_tile) = line.get(next_x) {
-                next_tile
-            } else {
-                return Err(MoveError::NoTiles);
-            }
-        } else {
-            return Err(MoveError::NoTiles);
-        };
-        match next_tile {
-            Tile::Ground | Tile::Water => (),
-            _ => return Err(MoveError::HitWall),
+        let (next_x, next_y) = match direction {
+            Direction::Up => (self.player.position.x as i32, self.player.position.y as i32 - 1),
+            Direction::Down => (self.player.position.x as i32, self.player.position.y as i32 + 1),
+            Direction::Left => (self.player.position.x as i32 - 1, self.player.position.y as i32),
+            Direction::Right => (self.player.position.x as i32 + 1, self.player.position.y as i32),
         };
+        if !Tile::is_walkable(self.world.tile(next_x, next_y)) {
+            return Err(MoveError::HitWall);
+        }
         self.player.position = Vector2 {
             x: next_x as f32,
             y: next_y as f32,
@@ -197,54 +435,202 @@ impl MapState {
     }
 
     pub fn may_break_something(&mut self, direction: Direction) -> Result<(), BreakError> {
-        let player_position = self.player.position;
-        let break_target = match direction {
-            Direction::Up => Vector2 {
-                x: player_position.x,
-                y: player_position.y - 1.0,
-            },
-            Direction::Down => Vector2 {
-                x: player_position.x,
-                y: player_position.y + 1.0,
-            },
-            Direction::Left => Vector2 {
-                x: player_position.x - 1.0,
-                y: player_position.y,
-            },
-            Direction::Right => Vector2 {
-                x: player_position.x + 1.0,
-                y: player_position.y,
-            },
+        let break_target = direction.next_to(self.player.position);
+        let mining = Tile::get_mining(self.world.tile(break_target.x as i32, break_target.y as i32))
+            .ok_or(BreakError::Unbreakable)?;
+        let tool = self.player.get_tool();
+        if tool.tier < mining.required_tier {
+            return Err(BreakError::ToolTierTooLow(mining.required_tier));
+        }
+        if !self.player.is_ready() {
+            return Err(BreakError::PlayerBusy);
+        }
+        self.player.animation_state = AnimationState {
+            current_frame: 0,
+            status: Status::Breaking,
+            cooldown: Some(mining.hardness / tool.speed),
+            target: Some(break_target),
+        };
+        Ok(())
+    }
+
+    /// Turns one item of the inventory into the tile its definition places on the target tile.
+    pub fn may_place_item(&mut self, direction: Direction, id: &str) -> Result<(), PlaceError> {
+        if self.player.count_item(id) == 0 {
+            return Err(PlaceError::NotInInventory);
+        }
+        let places = &ITEMS.get(id).ok_or(PlaceError::NotPlaceable)?.places;
+        if places.is_empty() {
+            return Err(PlaceError::NotPlaceable);
+        }
+        if !self.player.is_ready() {
+            return Err(PlaceError::PlayerBusy);
+        }
+        let target = direction.next_to(self.player.position);
+        let (x, y) = (target.x as i32, target.y as i32);
+        if self.items.iter().any(|item| item.position.x == target.x && item.position.y == target.y) {
+            return Err(PlaceError::Occupied);
+        }
+        if self.creature_at(x, y).is_some() {
+            return Err(PlaceError::Occupied);
+        }
+        let current = self.world.tile(x, y);
+        let (_, placed) = places
+            .iter()
+            .find(|(on, _)| on == current)
+            .ok_or(PlaceError::NotPlaceableHere)?;
+        self.world.set_tile(x, y, Tile::clone(placed));
+        self.player.remove_item_from_inventory(id, 1);
+        Ok(())
+    }
+
+    /// Advances the breaking cooldown by dt seconds, and once it is over turns the target into
+    /// ground, dropping the items of the broken tile there.
+    pub fn update_breaking(&mut self, dt: f32) {
+        if self.player.animation_state.status != Status::Breaking {
+            return;
+        }
+        let cooldown = self.player.animation_state.cooldown.expect("BUG - breaking cooldown has not been set");
+        if cooldown > 0.0 {
+            self.player.animation_state.cooldown = Some(cooldown - dt);
+            return;
+        }
+        let target = self.player.animation_state.target.expect("BUG - breaking target has not been set");
+        let mining = Tile::get_mining(self.world.tile(target.x as i32, target.y as i32));
+        self.world.set_tile(target.x as i32, target.y as i32, Tile::Ground);
+        for drop in mining.map_or(&[][..], |mining| mining.drops) {
+            self.spawn_item(&target, drop, 1);
+        }
+        self.player.animation_state = DEFAULT_ANIMATION;
+    }
+
+    /// Moves the first item stack under the player into the inventory. Returns whether there was
+    /// one.
+    pub fn loot(&mut self) -> bool {
+        let position = self.player.position;
+        let Some(index) = self.items.iter().position(|item| item.position.x == position.x && item.position.y == position.y) else {
+            return false;
         };
-        match self.tiles.get_mut(break_target.y as usize) {
-            Some(line) => match line.get_mut(break_target.x as usize) {
-                Some(tile) => match tile {
-                    Tile::Tree => {
-                        if !self.player.is_ready() {
-                            return Err(BreakError::PlayerBusy);
-                        }
-                        self.player.animation_state = AnimationState {
-                            current_frame: 0,
-                            status: Status::Breaking,
-                            cooldown: Some(3.0),
-                            target: Some(break_target),
-                        };
-                        return Ok(());
-                    }
-                    _ => Err(BreakError::Unbreakable),
-                },
-                None => Err(BreakError::Nothing),
-            },
-            None => Err(BreakError::Nothing),
-        }
-    }
-
-    pub fn spawn_item(&mut self, position: &Vector2, item: Box<dyn Item>) {
+        let item = self.items.remove(index);
+        for _ in 0..item.number {
+            self.player.add_item_in_inventory(&item.id);
+        }
+        true
+    }
+
+    pub fn spawn_item(&mut self, position: &Vector2, id: &str, number: i32) {
         self.items.push(MapItem {
             position: *position,
-            item,
+            id: id.to_string(),
+            number,
         });
     }
+
+    /// Hits the creature next to the player, harder with a better tool, and kills it once its
+    /// health is depleted.
+    pub fn may_attack(&mut self, direction: Direction) -> Result<(), AttackError> {
+        if !self.player.is_ready() || self.player.attack_cooldown > 0.0 {
+            return Err(AttackError::PlayerBusy);
+        }
+        let target = direction.next_to(self.player.position);
+        let index = self
+            .creature_at(target.x as i32, target.y as i32)
+            .ok_or(AttackError::NothingToAttack)?;
+        let damage = PLAYER_ATTACK_DAMAGE + PLAYER_ATTACK_DAMAGE_PER_TIER * self.player.get_tool().tier as f32;
+        self.player.attack_cooldown = PLAYER_ATTACK_DELAY;
+        self.creatures[index].health -= damage;
+        if self.creatures[index].health <= 0.0 {
+            self.creatures.remove(index);
+        }
+        Ok(())
+    }
+
+    /// Refills thirst when the player stands on or next to water.
+    pub fn may_drink(&mut self) -> Result<(), DrinkError> {
+        if !self.player.is_ready() {
+            return Err(DrinkError::PlayerBusy);
+        }
+        let (x, y) = (self.player.position.x as i32, self.player.position.y as i32);
+        let near_water = [(0, 0), (0, -1), (0, 1), (-1, 0), (1, 0)]
+            .iter()
+            .any(|(dx, dy)| *self.world.tile(x + dx, y + dy) == Tile::Water);
+        if !near_water {
+            return Err(DrinkError::NoWaterNearby);
+        }
+        self.player.thirst = (self.player.thirst + DRINK_AMOUNT).min(MAX_THIRST);
+        Ok(())
+    }
+
+    /// Drains hunger and thirst, applies the damage of lava under the player or of deprivation and
+    /// regeneration over dt seconds, and respawns the player at the spawn point once the death
+    /// screen is over.
+    pub fn update_health(&mut self, dt: f32) {
+        if let Some(cooldown) = self.player.respawn_cooldown {
+            if cooldown > 0.0 {
+                self.player.respawn_cooldown = Some(cooldown - dt);
+                return;
+            }
+            self.player.position = self.spawn_point;
+            self.player.previous_position = self.spawn_point;
+            self.player.health = MAX_HEALTH;
+            self.player.hunger = MAX_HUNGER;
+            self.player.thirst = MAX_THIRST;
+            self.player.since_damage = 0.0;
+            self.player.respawn_cooldown = None;
+            return;
+        }
+        self.player.hunger = (self.player.hunger - HUNGER_PER_SECOND * dt).max(0.0);
+        self.player.thirst = (self.player.thirst - THIRST_PER_SECOND * dt).max(0.0);
+        let weak = self.player.hunger < LOW_METER || self.player.thirst < LOW_METER;
+        self.player.velocity = if weak { WEAK_VELOCITY } else { BASE_VELOCITY };
+        self.player.light_vision = if weak { WEAK_LIGHT_VISION } else { BASE_LIGHT_VISION };
+        let position = self.player.position;
+        if *self.world.tile(position.x as i32, position.y as i32) == Tile::Lava {
+            self.damage_player(LAVA_DAMAGE_PER_SECOND * dt);
+            return;
+        }
+        if self.player.hunger == 0.0 || self.player.thirst == 0.0 {
+            self.damage_player(DEPRIVATION_DAMAGE_PER_SECOND * dt);
+            return;
+        }
+        self.player.since_damage += dt;
+        if self.player.since_damage >= REGENERATION_DELAY {
+            self.player.health = (self.player.health + REGENERATION_PER_SECOND * dt).min(MAX_HEALTH);
+        }
+    }
+
+    /// Removes health from the player, killing them once it is depleted.
+    pub fn damage_player(&mut self, amount: f32) {
+        if self.player.is_dead() {
+            return;
+        }
+        self.player.health -= amount;
+        self.player.since_damage = 0.0;
+        if self.player.health <= 0.0 {
+            self.kill_player();
+        }
+    }
+
+    /// Drops the inventory where the player died, but for the equipped tool, and starts the respawn
+    /// countdown.
+    fn kill_player(&mut self) {
+        let position = self.player.position;
+        self.path.clear();
+        let mut kept_tool = None;
+        for mut item in std::mem::take(&mut self.player.inventory) {
+            if kept_tool.is_none() && self.player.equipped_tool.as_ref() == Some(&item.id) {
+                kept_tool = Some(InventoryItem { number: 1, id: item.id.clone() });
+                item.number -= 1;
+            }
+            if item.number > 0 {
+                self.spawn_item(&position, &item.id, item.number);
+            }
+        }
+        self.player.inventory = kept_tool.into_iter().collect();
+        self.player.health = 0.0;
+        self.player.animation_state = DEFAULT_ANIMATION;
+        self.player.respawn_cooldown = Some(RESPAWN_DELAY);
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -260,6 +646,63 @@ pub enum Tile {
     Demonite = 8,
     Glitch = 9,
     Tree = 10,
+    Bridge = 11,
+    SteppingStone = 12,
+}
+
+impl Tile {
+    /// Whether the player can step on the tile, lava included. Glitches are holes, they can't be
+    /// walked on.
+    pub fn is_walkable(&self) -> bool {
+        matches!(self, Tile::Ground | Tile::Water | Tile::Bridge | Tile::SteppingStone | Tile::Lava)
+    }
+
+    /// Whether stepping on the tile hurts, so that paths and creatures avoid it.
+    pub fn is_hazard(&self) -> bool {
+        matches!(self, Tile::Lava | Tile::Glitch)
+    }
+
+    /// Hardness, required tool tier and drops of the tile, None for tiles that can't be mined.
+    pub fn get_mining(&self) -> Option<Mining> {
+        let mining = |hardness, required_tier, drops| Some(Mining { hardness, required_tier, drops });
+        match self {
+            Tile::Tree => mining(3.0, 0, &["wood", "apple"]),
+            Tile::Wall => mining(2.0, 1, &["stone"]),
+            Tile::Bronze => mining(3.0, 1, &["bronze_ore"]),
+            Tile::Silver => mining(4.0, 2, &["silver_ore"]),
+            Tile::Gold => mining(5.0, 2, &["gold_ore"]),
+            Tile::Mytril => mining(6.0, 3, &["mytril_ore"]),
+            Tile::Demonite => mining(8.0, 4, &["demonite_ore"]),
+            Tile::Ground | Tile::Water | Tile::Lava | Tile::Glitch | Tile::Bridge | Tile::SteppingStone => None,
+        }
+    }
+
+    /// Tile of the given discriminant, as saved in chunk files.
+    pub fn from_id(id: u8) -> Option<Tile> {
+        match id {
+            0 => Some(Tile::Ground),
+            1 => Some(Tile::Wall),
+            2 => Some(Tile::Water),
+            3 => Some(Tile::Lava),
+            4 => Some(Tile::Bronze),
+            5 => Some(Tile::Silver),
+            6 => Some(Tile::Gold),
+            7 => Some(Tile::Mytril),
+            8 => Some(Tile::Demonite),
+            9 => Some(Tile::Glitch),
+            10 => Some(Tile::Tree),
+            11 => Some(Tile::Bridge),
+            12 => Some(Tile::SteppingStone),
+            _ => None,
+        }
+    }
+
+    /// Tile named as by `get_tile_string`, as written in item definitions.
+    pub fn from_name(name: &str) -> Option<Tile> {
+        (0..=u8::MAX)
+            .map_while(Tile::from_id)
+            .find(|tile| get_tile_string(tile) == name)
+    }
 }
 
 pub fn get_tile_string(tile: &Tile) -> String {
@@ -275,6 +718,8 @@ pub fn get_tile_string(tile: &Tile) -> String {
         Tile::Demonite => "Demonite".to_string(),
         Tile::Glitch => "Glitch".to_string(),
         Tile::Tree => "Tree".to_string(),
+        Tile::Bridge => "Bridge".to_string(),
+        Tile::SteppingStone => "SteppingStone".to_string(),
     }
 }
 
@@ -292,110 +737,332 @@ impl DerefMut for Tile {
     }
 }
 
-pub fn init_map(width: u32, height: u32) {
+/// Moves the displayed player position one velocity step towards its tile position.
+pub fn process_player_position() {
     let mut map = MAP_STATE.lock().expect("Failed to get map state");
-    map.tiles = generate_map(width as usize, height as usize);
-    for (y, line) in map.tiles.iter().enumerate() {
-        for (x, tile) in line.iter().enumerate() {
-            if x >= (line.len() / 2) && y >= (map.tiles.len() / 2) {
-                if let Tile::Ground = tile {
-                    map.player.position.x = x as f32;
-                    map.player.position.y = y as f32;
-                    map.player.previous_position.x = x as f32;
-                    map.player.previous_position.y = y as f32;
-                    return;
-                }
-            }
-        }
+    if map.player.previous_position.x < map.player.position.x {
+        map.player.previous_position.x += map.player.velocity;
+    } else if map.player.previous_position.x > map.player.position.x {
+        map.player.previous_position.x -= map.player.velocity;
+    }
+    if map.player.previous_position.y < map.player.position.y {
+        map.player.previous_position.y += map.player.velocity;
+    } else if map.player.previous_position.y > map.player.position.y {
+        map.player.previous_position.y -= map.player.velocity;
+    }
+}
+
+/// Advances the breaking cooldown by dt seconds, and breaks the target once it is over.
+pub fn process_player_breaking(dt: f32) {
+    let mut map = MAP_STATE.lock().expect("Failed to get map state");
+    map.update_breaking(dt);
+}
+
+/// Updates hunger, thirst and health of the player over dt seconds.
+pub fn process_player_health(dt: f32) {
+    let mut map = MAP_STATE.lock().expect("Failed to get map state");
+    map.update_health(dt);
+}
+
+/// Advances the game clock by dt seconds.
+pub fn process_clock(dt: f32) {
+    let mut map = MAP_STATE.lock().expect("Failed to get map state");
+    map.clock.elapsed += dt;
+}
+
+/// Spawns, moves and despawns creatures over dt seconds, and cools the attack of the player down.
+pub fn process_creatures(dt: f32) {
+    let mut map = MAP_STATE.lock().expect("Failed to get map state");
+    map.player.attack_cooldown = (map.player.attack_cooldown - dt).max(0.0);
+    map.update_creatures(dt);
+}
+
+/// Makes the next queued move once the player is ready and has reached its tile, and drops the
+/// rest of the path when a move fails.
+pub fn process_player_path() {
+    let mut map = MAP_STATE.lock().expect("Failed to get map state");
+    let arrived = map.player.previous_position.x == map.player.position.x
+        && map.player.previous_position.y == map.player.position.y;
+    if !arrived || !map.player.is_ready() {
+        return;
+    }
+    let Some(direction) = map.path.pop_front() else {
+        return;
+    };
+    if map.may_move_player(direction).is_err() {
+        map.path.clear();
     }
 }
 
-pub fn generate_map(width: usize, height: usize) -> Vec<Vec<Tile>> {
+/// Loads the chunks around the player and evicts the far away ones.
+pub fn process_chunks() {
+    let mut map = MAP_STATE.lock().expect("Failed to get map state");
+    let position = map.player.position;
+    map.world.stream_around(position.x as i32, position.y as i32);
+}
+
+/// Id of the item players start with, equipped.
+const STARTING_TOOL: &str = "pickaxe";
+
+/// Creates the world of the seed and spawns the player on the first ground tile found from the
+/// origin, row by row. Only the chunks around the spawn are generated, the others as the player
+/// gets close.
+pub fn init_map(seed: u64, persistent: bool) {
     let now = SystemTime::now();
-    println!("GENERATING MAP...");
-    let mut rng = rand::rng();
-    let seed_map = rng.random_range(0..u32::MAX);
-    let hasher_map = PermutationTable::new(seed_map);
-    let map: NoiseMap = PlaneMapBuilder::new_fn(|point| perlin_2d(point.into(), &hasher_map))
-        .set_size(width, height)
-        .set_x_bounds(-50.0, 50.0)
-        .set_y_bounds(-50.0, 50.0)
-        .build();
-    let hasher_trees = PermutationTable::new(seed_map);
-    let trees: NoiseMap = PlaneMapBuilder::new_fn(|point| perlin_2d(point.into(), &hasher_trees))
-        .set_size(width, height)
-        .set_x_bounds(-50.0, 50.0)
-        .set_y_bounds(-50.0, 50.0)
-        .build();
-    let map: Vec<f64> = map.iter().copied().collect();
-    let trees: Vec<f64> = trees.iter().copied().collect();
-    let mut trees_iter = trees.iter();
-    let rows: Vec<Vec<Tile>> = (0..height as i32)
-        .map(|line| {
-            let index = line as usize * width;
-            let range = index..index + width - 1;
-            let row: Vec<Tile> = map[range]
-                .iter()
-                .map(|noise_value| to_tile(*noise_value))
-                .map(|tile| {
-                    may_be_tree(
-                        tile,
-                        trees_iter
-                            .next()
-                            .expect("Failed to get noise from trees map"),
-                    )
-                })
-                .collect();
-            row
-        })
-        .collect();
+    let mut map = MAP_STATE.lock().expect("Failed to get map state");
+    map.world = World::new(seed, persistent);
+    map.clock = Clock::start();
+    map.creatures = vec![];
+    map.path.clear();
+    map.creature_rng = Some(StdRng::seed_from_u64(seed));
+    // Players start with a pickaxe to mine walls and the first ores
+    map.player.inventory = vec![InventoryItem { number: 1, id: STARTING_TOOL.to_string() }];
+    map.player.equipped_tool = Some(STARTING_TOOL.to_string());
+    let (x, y) = map.world.spawn().unwrap_or_else(|| {
+        map.world.set_tile(0, 0, Tile::Ground);
+        (0, 0)
+    });
+    map.player.position = Vector2 { x: x as f32, y: y as f32 };
+    map.player.previous_position = map.player.position;
+    map.player.health = MAX_HEALTH;
+    map.player.hunger = MAX_HUNGER;
+    map.player.thirst = MAX_THIRST;
+    map.player.respawn_cooldown = None;
+    map.spawn_point = map.player.position;
+    map.world.stream_around(x, y);
     if let Ok(elapsed) = now.elapsed() {
-        println!(
-            "{} * {} map generated in {} ms",
-            width,
-            height,
+        eprintln!(
+            "World {} initialized with {} chunks in {} ms",
+            seed,
+            map.world.loaded_chunks_count(),
             elapsed.as_millis()
         )
     }
-    rows
 }
 
-fn may_be_tree(tile: Tile, noise_value: &f64) -> Tile {
-    if noise_value < &-0.5 && tile == Tile::Ground {
-        return Tile::Tree;
+#[cfg(test)]
+mod tests {
+    use raylib::ffi::Vector2;
+
+    use super::{
+        AnimationState, BreakError, Direction, DrinkError, EatError, MapState, MoveError, PlaceError, Status, Tile,
+        BASE_LIGHT_VISION, BASE_VELOCITY, DRINK_AMOUNT, HUNGER_PER_SECOND, MAX_HEALTH, MAX_HUNGER, MAX_THIRST,
+        THIRST_PER_SECOND, WEAK_LIGHT_VISION, WEAK_VELOCITY,
+    };
+    use crate::{
+        creature::{Creature, CreatureKind},
+        world::World,
+    };
+
+    /// Map of a generated world with the player standing on ground at the origin.
+    fn map_at_origin() -> MapState {
+        let mut map = MapState::empty();
+        map.world = World::new(1, false);
+        map.world.set_tile(0, 0, Tile::Ground);
+        map.player.position = Vector2 { x: 0.0, y: 0.0 };
+        map
     }
-    tile
-}
 
-fn to_tile(cell: f64) -> Tile {
-    if cell < 0.0 {
-        to_wall()
-    } else if cell > 0.5 && cell < 0.505 {
-        Tile::Lava
-    } else if cell < 0.8 {
-        Tile::Ground
-    } else {
-        Tile::Water
+    /// Step of the simulated breaking time, in seconds.
+    const BREAK_STEP: f32 = 0.05;
+
+    /// Map at the origin with the tool equipped, or with bare hands when None.
+    fn map_with_tool(tool: Option<&str>) -> MapState {
+        let mut map = map_at_origin();
+        if let Some(tool) = tool {
+            map.player.add_item_in_inventory(tool);
+            map.player.equipped_tool = Some(tool.to_string());
+        }
+        map
     }
-}
 
-fn to_wall() -> Tile {
-    let mut rng = rand::rng();
-    let percentage = rng.random_range(1..100);
-    if percentage < 99 {
-        Tile::Wall
-    } else {
-        let percentage = rng.random_range(1..100);
-        if percentage < 50 {
-            Tile::Bronze
-        } else if percentage < 70 {
-            Tile::Silver
-        } else if percentage < 85 {
-            Tile::Gold
-        } else if percentage < 95 {
-            Tile::Mytril
-        } else {
-            Tile::Demonite
+    /// Seconds spent breaking the tile right of the player, until it turns into ground.
+    fn break_time(map: &mut MapState, tile: Tile) -> f32 {
+        map.world.set_tile(1, 0, tile);
+        map.may_break_something(Direction::Right).expect("Tile should be breakable");
+        let mut elapsed = 0.0;
+        while *map.world.tile(1, 0) != Tile::Ground {
+            assert!(elapsed < 60.0, "Tile should break");
+            map.update_breaking(BREAK_STEP);
+            elapsed += BREAK_STEP;
         }
+        elapsed
+    }
+
+    #[test]
+    fn needs_a_tool_of_the_required_tier() {
+        let mut map = map_with_tool(None);
+        map.world.set_tile(1, 0, Tile::Wall);
+        assert!(matches!(map.may_break_something(Direction::Right), Err(BreakError::ToolTierTooLow(1))));
+        let mut map = map_with_tool(Some("pickaxe"));
+        map.world.set_tile(1, 0, Tile::Silver);
+        assert!(matches!(map.may_break_something(Direction::Right), Err(BreakError::ToolTierTooLow(2))));
+        map.update_breaking(60.0);
+        assert_eq!(*map.world.tile(1, 0), Tile::Silver);
+        map.world.set_tile(1, 0, Tile::Water);
+        assert!(matches!(map.may_break_something(Direction::Right), Err(BreakError::Unbreakable)));
+        let mut map = map_with_tool(Some("bronze_pickaxe"));
+        map.world.set_tile(1, 0, Tile::Silver);
+        map.may_break_something(Direction::Right).expect("Bronze pickaxes should break silver");
+    }
+
+    #[test]
+    fn breaks_in_the_hardness_divided_by_the_tool_speed() {
+        for (tool, tile) in [(None, Tile::Tree), (Some("pickaxe"), Tile::Wall), (Some("bronze_pickaxe"), Tile::Gold)] {
+            let mut map = map_with_tool(tool);
+            let expected = Tile::get_mining(&tile).expect("Tile should be minable").hardness / map.player.get_tool().speed;
+            let elapsed = break_time(&mut map, Tile::clone(&tile));
+            assert!(elapsed >= expected && elapsed <= expected + 2.0 * BREAK_STEP, "{:?} broke in {} instead of {}", tile, elapsed, expected);
+        }
+        let mut map = map_with_tool(Some("pickaxe"));
+        assert!(break_time(&mut map, Tile::Wall) < break_time(&mut map, Tile::Bronze));
+    }
+
+    #[test]
+    fn loots_the_drops_of_broken_tiles() {
+        let mut map = map_with_tool(None);
+        break_time(&mut map, Tile::Tree);
+        let dropped: Vec<&str> = map.items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(dropped, vec!["wood", "apple"]);
+        assert!(!map.loot(), "Drops should lie on the broken tile");
+        map.may_move_player(Direction::Right).expect("Broken tree should be walkable");
+        assert!(map.loot());
+        assert!(map.loot());
+        assert!(!map.loot());
+        assert_eq!((map.player.count_item("wood"), map.player.count_item("apple")), (1, 1));
+    }
+
+    /// Places the item on the given tile, right of the player, returning the tile left there.
+    fn place_on(map: &mut MapState, tile: Tile, id: &str) -> Result<Tile, PlaceError> {
+        map.world.set_tile(1, 0, tile);
+        map.may_place_item(Direction::Right, id)?;
+        Ok(Tile::clone(map.world.tile(1, 0)))
+    }
+
+    #[test]
+    fn places_walls_bridges_and_stepping_stones() {
+        let mut map = map_at_origin();
+        map.player.add_item_in_inventory("stone");
+        map.player.add_item_in_inventory("stone");
+        map.player.add_item_in_inventory("planks");
+        assert_eq!(place_on(&mut map, Tile::Ground, "stone").ok(), Some(Tile::Wall));
+        assert_eq!(place_on(&mut map, Tile::Lava, "stone").ok(), Some(Tile::SteppingStone));
+        assert_eq!(place_on(&mut map, Tile::Water, "planks").ok(), Some(Tile::Bridge));
+        assert_eq!((map.player.count_item("stone"), map.player.count_item("planks")), (0, 0));
+    }
+
+    #[test]
+    fn refuses_to_place_items_it_cannot() {
+        let mut map = map_at_origin();
+        assert!(matches!(place_on(&mut map, Tile::Ground, "stone"), Err(PlaceError::NotInInventory)));
+        map.player.add_item_in_inventory("wood");
+        assert!(matches!(place_on(&mut map, Tile::Ground, "wood"), Err(PlaceError::NotPlaceable)));
+        map.player.add_item_in_inventory("stone");
+        map.player.add_item_in_inventory("planks");
+        assert!(matches!(place_on(&mut map, Tile::Water, "stone"), Err(PlaceError::NotPlaceableHere)));
+        assert!(matches!(place_on(&mut map, Tile::Ground, "planks"), Err(PlaceError::NotPlaceableHere)));
+        assert!(matches!(place_on(&mut map, Tile::Wall, "stone"), Err(PlaceError::NotPlaceableHere)));
+
+        map.spawn_item(&Vector2 { x: 1.0, y: 0.0 }, "wood", 1);
+        assert!(matches!(place_on(&mut map, Tile::Ground, "stone"), Err(PlaceError::Occupied)));
+        map.items.clear();
+        map.creatures.push(Creature::new(CreatureKind::Wraith, 1, 0));
+        assert!(matches!(place_on(&mut map, Tile::Ground, "stone"), Err(PlaceError::Occupied)));
+        map.creatures.clear();
+
+        map.player.animation_state = AnimationState {
+            current_frame: 0,
+            status: Status::Breaking,
+            cooldown: Some(1.0),
+            target: None,
+        };
+        assert!(matches!(place_on(&mut map, Tile::Ground, "stone"), Err(PlaceError::PlayerBusy)));
+        assert_eq!((map.player.count_item("stone"), map.player.count_item("planks")), (1, 1));
+    }
+
+    #[test]
+    fn drains_hunger_and_thirst_over_time() {
+        let mut map = map_at_origin();
+        map.update_health(10.0);
+        assert_eq!(map.player.hunger, MAX_HUNGER - HUNGER_PER_SECOND * 10.0);
+        assert_eq!(map.player.thirst, MAX_THIRST - THIRST_PER_SECOND * 10.0);
+        assert_eq!(map.player.health, MAX_HEALTH);
+        map.update_health(1000.0);
+        assert_eq!((map.player.hunger, map.player.thirst), (0.0, 0.0));
+        assert!(map.player.health < MAX_HEALTH, "Empty meters should hurt");
+    }
+
+    #[test]
+    fn drinks_only_next_to_water() {
+        let mut map = map_at_origin();
+        for (x, y) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+            map.world.set_tile(x, y, Tile::Ground);
+        }
+        map.player.thirst = 10.0;
+        assert!(matches!(map.may_drink(), Err(DrinkError::NoWaterNearby)));
+        map.world.set_tile(1, 1, Tile::Water);
+        assert!(matches!(map.may_drink(), Err(DrinkError::NoWaterNearby)), "Diagonals are not next to the player");
+        map.world.set_tile(0, 1, Tile::Water);
+        map.may_drink().expect("Water below should be drinkable");
+        assert_eq!(map.player.thirst, 10.0 + DRINK_AMOUNT);
+        map.player.thirst = MAX_THIRST - 1.0;
+        map.may_drink().expect("Water below should be drinkable");
+        assert_eq!(map.player.thirst, MAX_THIRST);
+    }
+
+    #[test]
+    fn eats_apples_but_not_wood() {
+        let mut map = map_at_origin();
+        assert!(matches!(map.player.eat("apple"), Err(EatError::NotInInventory)));
+        map.player.add_item_in_inventory("wood");
+        assert!(matches!(map.player.eat("wood"), Err(EatError::NotFood)));
+        map.player.add_item_in_inventory("apple");
+        map.player.add_item_in_inventory("apple");
+        map.player.hunger = 50.0;
+        map.player.eat("apple").expect("Apples should be edible");
+        assert_eq!(map.player.hunger, 75.0);
+        map.player.eat("apple").expect("Apples should be edible");
+        assert_eq!(map.player.hunger, MAX_HUNGER);
+        assert_eq!(map.player.count_item("apple"), 0);
+    }
+
+    #[test]
+    fn weakens_the_player_while_a_meter_is_low() {
+        let mut map = map_at_origin();
+        for (hunger, thirst) in [(0.0, MAX_THIRST), (MAX_HUNGER, 0.0)] {
+            map.player.hunger = hunger;
+            map.player.thirst = thirst;
+            map.update_health(0.1);
+            assert_eq!((map.player.velocity, map.player.light_vision), (WEAK_VELOCITY, WEAK_LIGHT_VISION));
+        }
+        map.player.hunger = MAX_HUNGER;
+        map.player.thirst = MAX_THIRST;
+        map.update_health(0.1);
+        assert_eq!((map.player.velocity, map.player.light_vision), (BASE_VELOCITY, BASE_LIGHT_VISION));
+    }
+
+    #[test]
+    fn walks_on_lava_but_not_into_glitches() {
+        let mut map = map_at_origin();
+        map.world.set_tile(1, 0, Tile::Glitch);
+        assert!(matches!(map.may_move_player(Direction::Right), Err(MoveError::HitWall)));
+        map.world.set_tile(0, 1, Tile::Lava);
+        map.may_move_player(Direction::Down).expect("Lava should be walkable");
+        assert_eq!((map.player.position.x, map.player.position.y), (0.0, 1.0));
+    }
+
+    #[test]
+    fn keeps_the_equipped_tool_on_death() {
+        let mut map = map_at_origin();
+        map.player.add_item_in_inventory("pickaxe");
+        map.player.add_item_in_inventory("pickaxe");
+        map.player.add_item_in_inventory("wood");
+        map.player.equipped_tool = Some("pickaxe".to_string());
+        map.damage_player(MAX_HEALTH);
+        assert!(map.player.is_dead());
+        assert_eq!(map.player.count_item("pickaxe"), 1);
+        assert_eq!(map.player.count_item("wood"), 0);
+        let dropped: Vec<(&str, i32)> = map.items.iter().map(|item| (item.id.as_str(), item.number)).collect();
+        assert_eq!(dropped, vec![("pickaxe", 1), ("wood", 1)]);
     }
 }
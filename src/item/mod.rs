@@ -1,55 +1,36 @@
-use std::any::Any;
-
 use raylib::ffi::Vector2;
 
-pub trait Item {
-    fn get_name(&self) -> String;
-    fn to_inventory_item(&self) -> InventoryItem;
+pub mod recipe;
+pub mod registry;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tool {
+    /// Tiles requiring a higher tier can't be mined with the tool.
+    pub tier: u8,
+    /// Divides the hardness of the mined tile to get the break time in seconds.
+    pub speed: f32,
 }
 
+/// Tool used when nothing is equipped.
+pub const HAND: Tool = Tool { tier: 0, speed: 1.0 };
+
 pub struct MapItem {
     pub position: Vector2,
-    pub item: Box<dyn Item>,
+    /// Id of the item in the registry.
+    pub id: String,
+    pub number: i32,
 }
 
+#[derive(Debug, Clone)]
 pub struct InventoryItem {
     pub number: i32,
-    pub item: Box<dyn Item>,
+    /// Id of the item in the registry.
+    pub id: String,
 }
 
+/// Stacks of the same item are equal whatever their size.
 impl PartialEq for InventoryItem {
     fn eq(&self, other: &Self) -> bool {
-        self.type_id() == other.type_id()
-    }
-}
-
-unsafe impl Send for MapItem {}
-unsafe impl Send for InventoryItem {}
-
-#[derive(Clone)]
-pub struct Pickaxe {
-    
-}
-
-impl Item for Pickaxe {
-    fn get_name(&self) -> String {
-        "Pickaxe".to_string()
-    }
-
-    fn to_inventory_item(&self) -> InventoryItem {
-        InventoryItem { number: 0, item: Box::new(self.clone()) }
-    }
-}
-
-#[derive(Clone)]
-pub struct TreeItem {}
-
-impl Item for TreeItem {
-    fn get_name(&self) -> String {
-        "TreeItem".to_string()
-    }
-
-    fn to_inventory_item(&self) -> InventoryItem {
-        InventoryItem { number: 0, item: Box::new(self.clone()) }
+        self.id == other.id
     }
 }
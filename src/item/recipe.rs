@@ -0,0 +1,187 @@
+use std::{fs, io};
+
+use lazy_static::lazy_static;
+use serde_json::Value;
+
+use super::registry::ITEMS;
+
+/// File defining every crafting recipe of the game.
+pub const RECIPES_PATH: &str = "assets/items/recipes.json";
+
+lazy_static! {
+    pub static ref RECIPES: RecipeBook = RecipeBook::load(RECIPES_PATH)
+        .unwrap_or_else(|error| panic!("Failed to load recipes from {} : {:?}", RECIPES_PATH, error));
+}
+
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    /// Id of the crafted item, also used to refer to the recipe.
+    pub output: String,
+    pub count: i32,
+    /// Ids and counts of the items consumed by one craft.
+    pub inputs: Vec<(String, i32)>,
+}
+
+impl Recipe {
+    /// Describes the recipe as shown to players, e.g. `planks x4 : wood x1`.
+    pub fn to_display_string(&self) -> String {
+        let inputs: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|(id, count)| format!("{} x{}", id, count))
+            .collect();
+        format!("{} x{} : {}", self.output, self.count, inputs.join(", "))
+    }
+}
+
+#[derive(Debug)]
+pub enum RecipeBookError {
+    Io(io::Error),
+    InvalidJson(serde_json::Error),
+    ExpectedArray,
+    /// Index of the recipe and name of the missing or mistyped field.
+    InvalidField(usize, &'static str),
+    UnknownItem(String),
+    DuplicateRecipe(String),
+}
+
+pub struct RecipeBook {
+    recipes: Vec<Recipe>,
+}
+
+impl RecipeBook {
+    pub fn load(path: &str) -> Result<RecipeBook, RecipeBookError> {
+        let content = fs::read_to_string(path).map_err(RecipeBookError::Io)?;
+        RecipeBook::parse(&content)
+    }
+
+    /// Parses a JSON array of recipes, whose items must all be in the item registry.
+    pub fn parse(content: &str) -> Result<RecipeBook, RecipeBookError> {
+        let value: Value = serde_json::from_str(content).map_err(RecipeBookError::InvalidJson)?;
+        let entries = value.as_array().ok_or(RecipeBookError::ExpectedArray)?;
+        let mut recipes: Vec<Recipe> = vec![];
+        for (index, entry) in entries.iter().enumerate() {
+            let recipe = parse_recipe(index, entry)?;
+            let ids = std::iter::once(&recipe.output).chain(recipe.inputs.iter().map(|(id, _)| id));
+            for id in ids {
+                if ITEMS.get(id).is_none() {
+                    return Err(RecipeBookError::UnknownItem(id.clone()));
+                }
+            }
+            if recipes.iter().any(|other| other.output == recipe.output) {
+                return Err(RecipeBookError::DuplicateRecipe(recipe.output));
+            }
+            recipes.push(recipe);
+        }
+        Ok(RecipeBook { recipes })
+    }
+
+    pub fn get(&self, output: &str) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.output == output)
+    }
+
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+}
+
+fn parse_recipe(index: usize, entry: &Value) -> Result<Recipe, RecipeBookError> {
+    let count_field = |value: &Value, field: &'static str| {
+        value["count"]
+            .as_i64()
+            .filter(|count| *count > 0)
+            .map(|count| count as i32)
+            .ok_or(RecipeBookError::InvalidField(index, field))
+    };
+    let output = entry["output"]
+        .as_str()
+        .ok_or(RecipeBookError::InvalidField(index, "output"))?;
+    let inputs = entry["inputs"]
+        .as_array()
+        .filter(|inputs| !inputs.is_empty())
+        .ok_or(RecipeBookError::InvalidField(index, "inputs"))?
+        .iter()
+        .map(|input| {
+            let id = input["id"].as_str().ok_or(RecipeBookError::InvalidField(index, "inputs.id"))?;
+            Ok((id.to_string(), count_field(input, "inputs.count")?))
+        })
+        .collect::<Result<Vec<(String, i32)>, RecipeBookError>>()?;
+    Ok(Recipe {
+        output: output.to_string(),
+        count: count_field(entry, "count")?,
+        inputs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecipeBook, RecipeBookError, RECIPES_PATH};
+    use crate::game_state::{CraftError, Player};
+
+    fn parse_error(content: &str) -> RecipeBookError {
+        match RecipeBook::parse(content) {
+            Ok(_) => panic!("Recipes should be rejected : {}", content),
+            Err(error) => error,
+        }
+    }
+
+    fn player_with(items: &[(&str, i32)]) -> Player {
+        let mut player = Player::empty();
+        for (id, count) in items {
+            for _ in 0..*count {
+                player.add_item_in_inventory(id);
+            }
+        }
+        player
+    }
+
+    #[test]
+    fn loads_the_recipes_of_the_game() {
+        let recipes = RecipeBook::load(RECIPES_PATH).expect("Recipes of the game should load");
+        let planks = recipes.get("planks").expect("Planks should be craftable");
+        assert_eq!(planks.to_display_string(), "planks x4 : wood x1");
+    }
+
+    #[test]
+    fn rejects_invalid_recipes() {
+        assert!(matches!(parse_error("[{"), RecipeBookError::InvalidJson(_)));
+        assert!(matches!(
+            parse_error(r#"[{ "output": "planks", "count": 4, "inputs": [{ "id": "gem", "count": 1 }] }]"#),
+            RecipeBookError::UnknownItem(id) if id == "gem"
+        ));
+        assert!(matches!(
+            parse_error(r#"[{ "output": "planks", "count": 4, "inputs": [{ "id": "wood", "count": 0 }] }]"#),
+            RecipeBookError::InvalidField(0, "inputs.count")
+        ));
+        assert!(matches!(
+            parse_error(r#"[{ "output": "planks", "count": 4, "inputs": [] }]"#),
+            RecipeBookError::InvalidField(0, "inputs")
+        ));
+        assert!(matches!(
+            parse_error(r#"[{ "output": "planks", "count": 4, "inputs": [{ "id": "wood", "count": 1 }] },
+                           { "output": "planks", "count": 2, "inputs": [{ "id": "wood", "count": 1 }] }]"#),
+            RecipeBookError::DuplicateRecipe(output) if output == "planks"
+        ));
+    }
+
+    #[test]
+    fn crafts_by_consuming_inputs() {
+        let mut player = player_with(&[("wood", 3)]);
+        assert_eq!(player.craftable_times("planks").expect("Planks should be craftable"), 3);
+        player.craft("planks", 2).expect("Two crafts should be possible");
+        assert_eq!(player.count_item("wood"), 1);
+        assert_eq!(player.count_item("planks"), 8);
+    }
+
+    #[test]
+    fn refuses_crafts_without_enough_items() {
+        let mut player = player_with(&[("wood", 1), ("bronze_ore", 1)]);
+        assert!(matches!(player.craft("bronze_bar", 1), Err(CraftError::MissingItems)));
+        assert!(matches!(player.craft("planks", 2), Err(CraftError::MissingItems)));
+        assert!(matches!(player.craft("planks", 0), Err(CraftError::InvalidTimes(0))));
+        assert!(matches!(player.craft("planks", -1), Err(CraftError::InvalidTimes(-1))));
+        assert!(matches!(player.craft("wood", 1), Err(CraftError::UnknownRecipe(id)) if id == "wood"));
+        assert_eq!(player.count_item("wood"), 1);
+        assert_eq!(player.count_item("bronze_ore"), 1);
+    }
+}
@@ -0,0 +1,199 @@
+use std::{fs, io};
+
+use lazy_static::lazy_static;
+use serde_json::Value;
+
+use crate::game_state::Tile;
+
+use super::Tool;
+
+/// File defining every item of the game.
+pub const ITEMS_PATH: &str = "assets/items/items.json";
+
+lazy_static! {
+    pub static ref ITEMS: ItemRegistry = ItemRegistry::load(ITEMS_PATH)
+        .unwrap_or_else(|error| panic!("Failed to load item definitions from {} : {:?}", ITEMS_PATH, error));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemCategory {
+    Tool,
+    Material,
+    Ore,
+    Food,
+}
+
+impl ItemCategory {
+    fn from_name(name: &str) -> Option<ItemCategory> {
+        match name {
+            "tool" => Some(ItemCategory::Tool),
+            "material" => Some(ItemCategory::Material),
+            "ore" => Some(ItemCategory::Ore),
+            "food" => Some(ItemCategory::Food),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemDefinition {
+    /// Stable identifier that inventories, drops and recipes refer to.
+    pub id: String,
+    pub name: String,
+    pub texture: String,
+    pub max_stack: i32,
+    pub category: ItemCategory,
+    pub description: String,
+    /// Mining abilities of the item when equipped, if it is a tool.
+    pub tool: Option<Tool>,
+    /// Tiles the item can be placed on, with the tile it turns into.
+    pub places: Vec<(Tile, Tile)>,
+    /// Hunger restored by eating the item, if it is food.
+    pub nutrition: Option<f32>,
+}
+
+#[derive(Debug)]
+pub enum ItemRegistryError {
+    Io(io::Error),
+    InvalidJson(serde_json::Error),
+    ExpectedArray,
+    /// Index of the definition and name of the missing or mistyped field.
+    InvalidField(usize, &'static str),
+    UnknownCategory(String),
+    UnknownTile(String),
+    DuplicateId(String),
+}
+
+pub struct ItemRegistry {
+    definitions: Vec<ItemDefinition>,
+}
+
+impl ItemRegistry {
+    pub fn load(path: &str) -> Result<ItemRegistry, ItemRegistryError> {
+        let content = fs::read_to_string(path).map_err(ItemRegistryError::Io)?;
+        ItemRegistry::parse(&content)
+    }
+
+    /// Parses a JSON array of item definitions.
+    pub fn parse(content: &str) -> Result<ItemRegistry, ItemRegistryError> {
+        let value: Value = serde_json::from_str(content).map_err(ItemRegistryError::InvalidJson)?;
+        let entries = value.as_array().ok_or(ItemRegistryError::ExpectedArray)?;
+        let mut definitions: Vec<ItemDefinition> = vec![];
+        for (index, entry) in entries.iter().enumerate() {
+            let definition = parse_definition(index, entry)?;
+            if definitions.iter().any(|other| other.id == definition.id) {
+                return Err(ItemRegistryError::DuplicateId(definition.id));
+            }
+            definitions.push(definition);
+        }
+        Ok(ItemRegistry { definitions })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ItemDefinition> {
+        self.definitions.iter().find(|definition| definition.id == id)
+    }
+
+    pub fn definitions(&self) -> &[ItemDefinition] {
+        &self.definitions
+    }
+}
+
+fn parse_definition(index: usize, entry: &Value) -> Result<ItemDefinition, ItemRegistryError> {
+    let string_field = |field: &'static str| {
+        entry[field].as_str().map(str::to_string).ok_or(ItemRegistryError::InvalidField(index, field))
+    };
+    let category = string_field("category")?;
+    let tool = match &entry["tool"] {
+        Value::Null => None,
+        tool => Some(Tool {
+            tier: tool["tier"].as_u64().ok_or(ItemRegistryError::InvalidField(index, "tool.tier"))? as u8,
+            speed: tool["speed"].as_f64().ok_or(ItemRegistryError::InvalidField(index, "tool.speed"))? as f32,
+        }),
+    };
+    let tile = |name: &str| Tile::from_name(name).ok_or_else(|| ItemRegistryError::UnknownTile(name.to_string()));
+    let mut places = vec![];
+    if let Some(entries) = entry["places"].as_object() {
+        for (on, placed) in entries {
+            let placed = placed.as_str().ok_or(ItemRegistryError::InvalidField(index, "places"))?;
+            places.push((tile(on)?, tile(placed)?));
+        }
+    }
+    Ok(ItemDefinition {
+        id: string_field("id")?,
+        name: string_field("name")?,
+        texture: string_field("texture")?,
+        max_stack: entry["maxStack"]
+            .as_i64()
+            .filter(|max_stack| *max_stack > 0)
+            .ok_or(ItemRegistryError::InvalidField(index, "maxStack"))? as i32,
+        category: ItemCategory::from_name(&category).ok_or(ItemRegistryError::UnknownCategory(category))?,
+        description: string_field("description")?,
+        tool,
+        places,
+        nutrition: entry["nutrition"].as_f64().map(|nutrition| nutrition as f32),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ItemCategory, ItemRegistry, ItemRegistryError, ITEMS_PATH};
+    use crate::game_state::Tile;
+
+    fn parse_error(content: &str) -> ItemRegistryError {
+        match ItemRegistry::parse(content) {
+            Ok(_) => panic!("Items should be rejected : {}", content),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn loads_the_item_definitions_of_the_game() {
+        let registry = ItemRegistry::load(ITEMS_PATH).expect("Items of the game should load");
+        let pickaxe = registry.get("pickaxe").expect("Players start with a pickaxe");
+        assert_eq!(pickaxe.category, ItemCategory::Tool);
+        assert!(pickaxe.tool.is_some());
+        assert!(registry.get("unknown").is_none());
+    }
+
+    #[test]
+    fn parses_optional_fields() {
+        let registry = ItemRegistry::parse(
+            r#"[{ "id": "stone", "name": "Stone", "texture": "stone.png", "maxStack": 64, "category": "material",
+                 "description": "Heavy.", "places": { "Water": "Ground" } },
+                { "id": "apple", "name": "Apple", "texture": "apple.png", "maxStack": 16, "category": "food",
+                 "description": "Crunchy.", "nutrition": 20 }]"#,
+        )
+        .expect("Items should parse");
+        let stone = registry.get("stone").expect("Stone should be defined");
+        assert_eq!(stone.places, vec![(Tile::Water, Tile::Ground)]);
+        assert_eq!(stone.nutrition, None);
+        assert_eq!(registry.get("apple").and_then(|apple| apple.nutrition), Some(20.0));
+    }
+
+    #[test]
+    fn rejects_malformed_definitions() {
+        assert!(matches!(parse_error("[{"), ItemRegistryError::InvalidJson(_)));
+        assert!(matches!(parse_error("{}"), ItemRegistryError::ExpectedArray));
+        assert!(matches!(
+            parse_error(r#"[{ "id": "wood", "name": "Wood", "texture": "wood.png", "maxStack": 0, "category": "material", "description": "" }]"#),
+            ItemRegistryError::InvalidField(0, "maxStack")
+        ));
+        assert!(matches!(
+            parse_error(r#"[{ "id": "wood", "name": "Wood", "texture": "wood.png", "maxStack": 64, "category": "material" }]"#),
+            ItemRegistryError::InvalidField(0, "description")
+        ));
+        assert!(matches!(
+            parse_error(r#"[{ "id": "wood", "name": "Wood", "texture": "wood.png", "maxStack": 64, "category": "weapon", "description": "" }]"#),
+            ItemRegistryError::UnknownCategory(category) if category == "weapon"
+        ));
+        assert!(matches!(
+            parse_error(r#"[{ "id": "wood", "name": "Wood", "texture": "wood.png", "maxStack": 64, "category": "material", "description": "", "places": { "Cloud": "Ground" } }]"#),
+            ItemRegistryError::UnknownTile(tile) if tile == "Cloud"
+        ));
+        assert!(matches!(
+            parse_error(r#"[{ "id": "wood", "name": "Wood", "texture": "wood.png", "maxStack": 64, "category": "material", "description": "" },
+                           { "id": "wood", "name": "Oak", "texture": "oak.png", "maxStack": 64, "category": "material", "description": "" }]"#),
+            ItemRegistryError::DuplicateId(id) if id == "wood"
+        ));
+    }
+}
@@ -0,0 +1,28 @@
+#[cfg(feature = "game")]
+pub mod animation;
+#[cfg(feature = "game")]
+pub mod biome;
+#[cfg(feature = "game")]
+pub mod cli;
+#[cfg(feature = "game")]
+pub mod clock;
+#[cfg(feature = "game")]
+pub mod creature;
+pub mod editor;
+#[cfg(feature = "game")]
+pub mod game_state;
+#[cfg(feature = "game")]
+pub mod item;
+pub mod lsp;
+#[cfg(feature = "game")]
+pub mod pathfinding;
+#[cfg(feature = "game")]
+pub mod scenes;
+#[cfg(feature = "game")]
+pub mod textures;
+#[cfg(feature = "game")]
+pub mod world;
+
+pub const TILE_SIZE: u8 = 32;
+
+pub const GET_EDITOR_STATE_ERROR: &str = "Failed to get editor state";
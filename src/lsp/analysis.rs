@@ -0,0 +1,206 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::editor::{
+    functions::{FunctionDef, FUNCTIONS},
+    grammar::{resolve_ast, resolve_located_ast, AstParseError, Expression, FunctionDefinition},
+    tokenizer::{get_current_token, get_prompt_tokens, scan_prompt_tokens, Literal, Token, TokenType, TokenizerError},
+};
+
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+pub struct Diagnostic {
+    pub range: Range<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Function defined in a script with `fun name(...) { ... }`.
+pub struct ScriptFunction {
+    pub function: FunctionDef,
+    /// Byte range of the function name.
+    pub name_range: Range<usize>,
+}
+
+pub struct FunctionInfo {
+    pub name: String,
+    pub signature: String,
+    pub doc: String,
+    pub insert_text: String,
+}
+
+impl FunctionInfo {
+    fn from_function(function: &FunctionDef) -> FunctionInfo {
+        FunctionInfo {
+            name: function.name.clone(),
+            signature: function.to_signature_string(),
+            doc: function.doc.clone(),
+            insert_text: function.to_complete_string(),
+        }
+    }
+}
+
+/// Reports tokenizer and grammar errors, and calls to functions that are neither native nor
+/// defined in the script.
+pub fn get_diagnostics(text: &str) -> Vec<Diagnostic> {
+    let (tokens, error) = scan_prompt_tokens(text);
+    if let Some((start, error)) = error {
+        return vec![Diagnostic {
+            range: start..text.len(),
+            severity: Severity::Error,
+            message: tokenizer_error_message(&error).to_string(),
+        }];
+    }
+    let mut diagnostics = vec![];
+    if let Ok(Err((range, error))) = get_prompt_tokens(text.to_string()).map(resolve_located_ast) {
+        diagnostics.push(Diagnostic {
+            range,
+            severity: Severity::Error,
+            message: ast_error_message(&error).to_string(),
+        });
+    }
+    let script_functions = get_script_functions(text);
+    for (index, token) in tokens.iter().enumerate() {
+        let Some(name) = called_function_name(&tokens, index) else {
+            continue;
+        };
+        let is_defined = script_functions.iter().any(|script_function| script_function.function.name == name)
+            || FUNCTIONS.lock().expect("Failed to resolve functions").iter().any(|function| function.name == name);
+        if !is_defined {
+            diagnostics.push(Diagnostic {
+                range: token.start..token.end,
+                severity: Severity::Warning,
+                message: format!("Unknown function {}", name),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Functions defined in the script. Definitions are found from tokens so they are still
+/// available while another part of the script does not parse.
+pub fn get_script_functions(text: &str) -> Vec<ScriptFunction> {
+    let (tokens, _) = scan_prompt_tokens(text);
+    let definitions: Vec<Expression> = get_prompt_tokens(text.to_string())
+        .ok()
+        .and_then(|tokens| resolve_ast(tokens).ok())
+        .map(|ast| ast.tree)
+        .unwrap_or_default();
+    tokens
+        .windows(2)
+        .filter_map(|window| match window {
+            [Token { literal: Some(Literal::Identifier(TokenType::FUN)), .. }, name_token @ Token { literal: Some(Literal::Label(name)), .. }] => {
+                let function = definitions
+                    .iter()
+                    .find_map(|expression| match expression {
+                        Expression::Definition(definition) if definition.name == *name => {
+                            Some(FunctionDef::from_definition(definition, Arc::default()))
+                        }
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| {
+                        let definition = FunctionDefinition {
+                            name: name.clone(),
+                            parameters: vec![],
+                            body: vec![],
+                            lines: vec![],
+                            starts: vec![],
+                            end: 0,
+                            doc: String::new(),
+                        };
+                        FunctionDef::from_definition(&definition, Arc::default())
+                    });
+                Some(ScriptFunction { function, name_range: name_token.start..name_token.end })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Native and script functions matching the token before the offset.
+pub fn get_completions(text: &str, offset: usize) -> Vec<FunctionInfo> {
+    let prefix: Vec<char> = text[..offset].chars().collect();
+    let token = get_current_token(&prefix).unwrap_or_default();
+    let mut scored: Vec<(i32, FunctionInfo)> = vec![];
+    for script_function in get_script_functions(text) {
+        if let Some(score) = script_function.function.matching_score(&token) {
+            scored.push((score, FunctionInfo::from_function(&script_function.function)));
+        }
+    }
+    let functions = FUNCTIONS.lock().expect("Failed to resolve functions");
+    for function in functions.iter() {
+        let is_shadowed = scored.iter().any(|(_, info)| info.name == function.name);
+        if let (Some(score), false) = (function.matching_score(&token), is_shadowed) {
+            scored.push((score, FunctionInfo::from_function(function)));
+        }
+    }
+    scored.sort_by(|(left_score, left), (right_score, right)| {
+        right_score.cmp(left_score).then_with(|| left.name.cmp(&right.name))
+    });
+    scored.into_iter().map(|(_, info)| info).collect()
+}
+
+/// Signature and documentation of the function named at the offset.
+pub fn get_hover(text: &str, offset: usize) -> Option<(Range<usize>, FunctionInfo)> {
+    let (range, name) = get_label_at(text, offset)?;
+    if let Some(script_function) = get_script_functions(text).into_iter().find(|script_function| script_function.function.name == name) {
+        return Some((range, FunctionInfo::from_function(&script_function.function)));
+    }
+    let functions = FUNCTIONS.lock().expect("Failed to resolve functions");
+    let function = functions.iter().find(|function| function.name == name)?;
+    Some((range, FunctionInfo::from_function(function)))
+}
+
+/// Range of the name in the definition of the script function named at the offset.
+pub fn get_definition(text: &str, offset: usize) -> Option<Range<usize>> {
+    let (_, name) = get_label_at(text, offset)?;
+    get_script_functions(text)
+        .into_iter()
+        .find(|script_function| script_function.function.name == name)
+        .map(|script_function| script_function.name_range)
+}
+
+fn get_label_at(text: &str, offset: usize) -> Option<(Range<usize>, String)> {
+    let (tokens, _) = scan_prompt_tokens(text);
+    tokens.into_iter().find_map(|token| match token.literal {
+        Some(Literal::Label(name)) if token.start <= offset && offset <= token.end => Some((token.start..token.end, name)),
+        _ => None,
+    })
+}
+
+fn called_function_name(tokens: &[Token], index: usize) -> Option<String> {
+    let is_call = tokens.get(index + 1).is_some_and(|next| next.token_type == TokenType::LEFT_PAREN);
+    let is_definition = index > 0 && matches!(tokens[index - 1].literal, Some(Literal::Identifier(TokenType::FUN)));
+    match &tokens[index].literal {
+        Some(Literal::Label(name)) if is_call && !is_definition => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn tokenizer_error_message(error: &TokenizerError) -> &'static str {
+    match error {
+        TokenizerError::TokenScanError => "Unexpected character",
+        TokenizerError::StringTokenScanError => "Unterminated string, any \" must match another \"",
+        TokenizerError::IdentifierMissmatch => "Invalid identifier, use a valid keyword instead",
+        TokenizerError::InvalidFunctionSyntax => "Invalid function syntax",
+        TokenizerError::NoIdentifierNorFunctionError => "No matching keyword nor function",
+        TokenizerError::UnterminatedComment => "Unterminated comment, any /* must match a */",
+    }
+}
+
+fn ast_error_message(error: &AstParseError) -> &'static str {
+    match error {
+        AstParseError::TokenInvalidGrammar => "Invalid grammar",
+        AstParseError::MissingLiteralForNumber => "Missing value for parsed number",
+        AstParseError::MissingLiteralForString => "Missing value for parsed String",
+        AstParseError::MissingLiteralForIdentifier => "Missing value for parsed Identifier",
+        AstParseError::UnaryWithNoValidNextToken => "Invalid value passed after ! or -",
+        AstParseError::InvalidFactorExpressions => "Invalid values passed to operation",
+        AstParseError::LabelWithNoValidNextToken => "Invalid values passed after label",
+        AstParseError::InvalidTokensInGroup => "Invalid values passed to () group",
+        AstParseError::InvalidFunctionDefinition => "Invalid function definition, expected fun name(parameters) { instructions }",
+        AstParseError::UnclosedBlock => "Missing } to close the block",
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    ops::Range,
+};
+
+use analysis::{get_completions, get_definition, get_diagnostics, get_hover, Severity};
+use serde_json::{json, Value};
+
+pub mod analysis;
+
+const CONTENT_LENGTH_HEADER: &str = "Content-Length: ";
+const METHOD_NOT_FOUND: i64 = -32601;
+const COMPLETION_KIND_FUNCTION: i64 = 3;
+const TEXT_DOCUMENT_SYNC_FULL: i64 = 1;
+
+#[derive(Debug)]
+pub enum LspError {
+    Io(io::Error),
+    MissingContentLength,
+    InvalidMessage(serde_json::Error),
+}
+
+/// Serves the language server protocol over stdin and stdout until the client exits.
+pub fn run_server() -> Result<(), LspError> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut output = io::stdout();
+    let mut documents: HashMap<String, String> = HashMap::new();
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(LspError::Io(error)) => return Err(LspError::Io(error)),
+            Err(error) => {
+                eprintln!("Skipped malformed message : {:?}", error);
+                continue;
+            }
+        };
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        if method == "exit" {
+            return Ok(());
+        }
+        if let Some(uri) = params["textDocument"]["uri"].as_str() {
+            match method {
+                "textDocument/didOpen" => {
+                    let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                    documents.insert(uri.to_string(), text.to_string());
+                }
+                // Documents are synchronised in full, the last change holds the whole text
+                "textDocument/didChange" => {
+                    if let Some(text) = params["contentChanges"].as_array().and_then(|changes| changes.last()) {
+                        documents.insert(uri.to_string(), text["text"].as_str().unwrap_or_default().to_string());
+                    }
+                }
+                "textDocument/didClose" => {
+                    documents.remove(uri);
+                }
+                _ => (),
+            }
+            if matches!(method, "textDocument/didOpen" | "textDocument/didChange" | "textDocument/didClose") {
+                let text = documents.get(uri).cloned().unwrap_or_default();
+                write_message(&mut output, &publish_diagnostics(uri, &text))?;
+            }
+        }
+        let Some(id) = message.get("id") else {
+            continue;
+        };
+        let response = match handle_request(method, params, &documents) {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": METHOD_NOT_FOUND, "message": format!("Unhandled method {}", method) },
+            }),
+        };
+        write_message(&mut output, &response)?;
+    }
+}
+
+fn handle_request(method: &str, params: &Value, documents: &HashMap<String, String>) -> Option<Value> {
+    let document = params["textDocument"]["uri"]
+        .as_str()
+        .map(|uri| (uri, documents.get(uri).map(String::as_str).unwrap_or_default()));
+    match (method, document) {
+        ("initialize", _) => Some(json!({
+            "capabilities": {
+                "textDocumentSync": TEXT_DOCUMENT_SYNC_FULL,
+                "completionProvider": {},
+                "hoverProvider": true,
+                "definitionProvider": true,
+            },
+            "serverInfo": { "name": "coding-survivor-lsp" },
+        })),
+        ("shutdown", _) => Some(Value::Null),
+        ("textDocument/completion", Some((_, text))) => {
+            let offset = position_to_offset(text, &params["position"]);
+            let items: Vec<Value> = get_completions(text, offset)
+                .into_iter()
+                .map(|info| json!({
+                    "label": info.name,
+                    "kind": COMPLETION_KIND_FUNCTION,
+                    "detail": info.signature,
+                    "documentation": info.doc,
+                    "insertText": info.insert_text,
+                }))
+                .collect();
+            Some(json!(items))
+        }
+        ("textDocument/hover", Some((_, text))) => {
+            let offset = position_to_offset(text, &params["position"]);
+            Some(match get_hover(text, offset) {
+                Some((range, info)) => json!({
+                    "contents": { "kind": "markdown", "value": format!("```\n{}\n```\n{}", info.signature, info.doc) },
+                    "range": to_lsp_range(text, range),
+                }),
+                None => Value::Null,
+            })
+        }
+        ("textDocument/definition", Some((uri, text))) => {
+            let offset = position_to_offset(text, &params["position"]);
+            Some(match get_definition(text, offset) {
+                Some(range) => json!({ "uri": uri, "range": to_lsp_range(text, range) }),
+                None => Value::Null,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn publish_diagnostics(uri: &str, text: &str) -> Value {
+    let diagnostics: Vec<Value> = get_diagnostics(text)
+        .into_iter()
+        .map(|diagnostic| json!({
+            "range": to_lsp_range(text, diagnostic.range),
+            "severity": match diagnostic.severity {
+                Severity::Error => 1,
+                Severity::Warning => 2,
+            },
+            "source": "coding-survivor",
+            "message": diagnostic.message,
+        }))
+        .collect();
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>, LspError> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).map_err(LspError::Io)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix(CONTENT_LENGTH_HEADER) {
+            content_length = length.parse::<usize>().ok();
+        }
+    }
+    let mut content = vec![0; content_length.ok_or(LspError::MissingContentLength)?];
+    input.read_exact(&mut content).map_err(LspError::Io)?;
+    serde_json::from_slice(&content).map(Some).map_err(LspError::InvalidMessage)
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<(), LspError> {
+    let content = message.to_string();
+    write!(output, "{}{}\r\n\r\n{}", CONTENT_LENGTH_HEADER, content.len(), content).map_err(LspError::Io)?;
+    output.flush().map_err(LspError::Io)
+}
+
+/// Converts an LSP position, counted in UTF-16 code units, to a byte offset in the text.
+fn position_to_offset(text: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or_default() as usize;
+    let character = position["character"].as_u64().unwrap_or_default() as usize;
+    let line_start: usize = text.split_inclusive('\n').take(line).map(str::len).sum();
+    let mut utf16_count = 0;
+    for (index, text_character) in text[line_start..].char_indices() {
+        if utf16_count >= character || text_character == '\n' {
+            return line_start + index;
+        }
+        utf16_count += text_character.len_utf16();
+    }
+    text.len()
+}
+
+fn offset_to_position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+    json!({ "line": line, "character": character })
+}
+
+fn to_lsp_range(text: &str, range: Range<usize>) -> Value {
+    json!({ "start": offset_to_position(text, range.start), "end": offset_to_position(text, range.end) })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::json;
+
+    use super::{offset_to_position, position_to_offset, read_message, LspError};
+
+    #[test]
+    fn converts_positions_to_offsets_and_back() {
+        let text = "moveUp()\n// é𝄞\nloot()";
+        let cases = [((0, 0), 0), ((0, 4), 4), ((1, 3), 12), ((1, 4), 14), ((1, 6), 18), ((2, 2), 21)];
+        for ((line, character), offset) in cases {
+            let position = json!({ "line": line, "character": character });
+            assert_eq!(position_to_offset(text, &position), offset, "position {:?}", position);
+            assert_eq!(offset_to_position(text, offset), position, "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn clamps_positions_past_the_end_of_a_line() {
+        let text = "moveUp()\nloot()";
+        assert_eq!(position_to_offset(text, &json!({ "line": 0, "character": 40 })), 8);
+        assert_eq!(position_to_offset(text, &json!({ "line": 5, "character": 0 })), text.len());
+    }
+
+    #[test]
+    fn reads_messages_after_a_malformed_one() {
+        let valid = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+        let input = format!("Content-Length: 8\r\n\r\nnot json{}{}\r\n\r\n{}", super::CONTENT_LENGTH_HEADER, valid.len(), valid);
+        let mut input = Cursor::new(input);
+        assert!(matches!(read_message(&mut input), Err(LspError::InvalidMessage(_))));
+        let message = read_message(&mut input).expect("Message should be read").expect("Message should exist");
+        assert_eq!(message["method"], "exit");
+        assert!(matches!(read_message(&mut input), Ok(None)));
+    }
+}
@@ -1,33 +1,39 @@
-use std::{collections::HashMap, sync::Mutex};
+use std::{collections::HashMap, env, process, sync::Mutex};
 
-use animation::Animation;
-use editor::keyboard::{editor_processing, start_keyboard_thread};
-use game_state::{init_map, Tile, MAP_STATE};
-use item::{InventoryItem, Pickaxe, TreeItem};
+use coding_survivor::{
+    animation::Animation,
+    cli::{game_seed, print_game_option_error, run_command},
+    editor::{
+        history::load_history,
+        keyboard::{editor_processing, start_keyboard_thread},
+    },
+    game_state::{init_map, EDITOR_STATE, MAP_STATE},
+    scenes::main_scene::main_scene,
+    textures::{load_map_texture, load_player_animations},
+    GET_EDITOR_STATE_ERROR,
+};
 use raylib::{texture::Texture2D, RaylibHandle, RaylibThread};
-use scenes::main_scene::main_scene;
-use textures::{load_map_texture, load_player_animations};
-
-mod animation;
-mod editor;
-mod game_state;
-mod item;
-mod scenes;
-mod textures;
 
 const GAME_NAME: &str = "Coding Survivor";
 const TARGET_FPS: u32 = 60;
-const GAME_WIDTH: u32 = 1000;
-const GAME_HEIGHT: u32 = 1000;
-const TILE_SIZE: u8 = 32;
-
-const GET_EDITOR_STATE_ERROR: &str = "Failed to get editor state";
 
 type SceneFnPointer = fn(&mut RaylibHandle, &RaylibThread, i32, i32, &HashMap<String, Texture2D>, &[Animation]);
 static CURRENT_SCENE: Mutex<SceneFnPointer> = Mutex::new(main_scene);
 
 fn main() {
-    init_map(GAME_WIDTH, GAME_HEIGHT);
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if let Some(command) = arguments.first().filter(|argument| !argument.starts_with("--")) {
+        process::exit(run_command(command, &arguments[1..]));
+    }
+    let seed = match game_seed(&arguments) {
+        Ok(seed) => seed,
+        Err(error) => {
+            print_game_option_error(&error);
+            process::exit(2);
+        }
+    };
+
+    init_map(seed, true);
 
     #[cfg(feature = "dev-only")]
     use_dev_pannel();
@@ -41,6 +47,7 @@ fn main() {
     rl.set_exit_key(None);
     rl.set_target_fps(TARGET_FPS);
 
+    load_history(&mut EDITOR_STATE.lock().expect(GET_EDITOR_STATE_ERROR));
     start_keyboard_thread();
     editor_processing();
     let map_textures = load_map_texture(&mut rl, &thread);
@@ -51,11 +58,12 @@ fn main() {
         let (game_width, game_height) = (rl.get_screen_width(), rl.get_screen_height());
         current_scene(&mut rl, &thread, game_width, game_height, &map_textures, &animations);
     }
+    MAP_STATE.lock().expect("Failed to get map state").world.save_all();
 }
 
 #[cfg(feature = "dev-only")]
 fn use_dev_pannel() {
-    use scenes::dev_pannel_scene::dev_pannel_scene;
+    use coding_survivor::scenes::dev_pannel_scene::dev_pannel_scene;
 
     let mut current_scene = CURRENT_SCENE.lock().expect("Failed to get current scene");
     *current_scene = dev_pannel_scene;
@@ -0,0 +1,175 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::game_state::{Direction, MapState, Tile};
+
+/// Tiles explored before giving up, so that a search stays fast on an endless world.
+pub const MAX_SEARCHED_TILES: usize = 20_000;
+
+/// Tile each explored tile was reached from, with the move made.
+type CameFrom = HashMap<(i32, i32), ((i32, i32), Direction)>;
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+#[derive(Debug)]
+pub enum PathError {
+    /// The target can't be walked on, or is walled off from the player.
+    Unreachable,
+    /// The search explored `MAX_SEARCHED_TILES` tiles without reaching the target.
+    SearchLimitReached,
+}
+
+/// Paths walk on the tiles the player can step on, avoiding hazards other than the target.
+fn is_path_tile(tile: &Tile) -> bool {
+    Tile::is_walkable(tile) && !Tile::is_hazard(tile)
+}
+
+fn manhattan_distance((x, y): (i32, i32), (target_x, target_y): (i32, i32)) -> i32 {
+    (x - target_x).abs() + (y - target_y).abs()
+}
+
+fn offset(direction: Direction) -> (i32, i32) {
+    match direction {
+        Direction::Up => (0, -1),
+        Direction::Down => (0, 1),
+        Direction::Left => (-1, 0),
+        Direction::Right => (1, 0),
+    }
+}
+
+impl MapState {
+    /// Shortest list of moves from the player to the target tile, found with A*.
+    pub fn find_path(&mut self, target: (i32, i32)) -> Result<Vec<Direction>, PathError> {
+        let start = (self.player.position.x as i32, self.player.position.y as i32);
+        if start == target {
+            return Ok(vec![]);
+        }
+        if !Tile::is_walkable(self.world.tile(target.0, target.1)) {
+            return Err(PathError::Unreachable);
+        }
+        let mut open = BinaryHeap::new();
+        let mut costs: HashMap<(i32, i32), i32> = HashMap::from([(start, 0)]);
+        let mut came_from: CameFrom = HashMap::new();
+        open.push(Reverse((manhattan_distance(start, target), 0, start)));
+        let mut searched_tiles = 0;
+        while let Some(Reverse((_, cost, position))) = open.pop() {
+            if position == target {
+                return Ok(rebuild_path(&came_from, start, target));
+            }
+            if cost > costs[&position] {
+                continue;
+            }
+            searched_tiles += 1;
+            if searched_tiles > MAX_SEARCHED_TILES {
+                return Err(PathError::SearchLimitReached);
+            }
+            for direction in DIRECTIONS {
+                let (dx, dy) = offset(direction);
+                let next = (position.0 + dx, position.1 + dy);
+                if next != target && !is_path_tile(self.world.tile(next.0, next.1)) {
+                    continue;
+                }
+                let next_cost = cost + 1;
+                if costs.get(&next).is_some_and(|known_cost| *known_cost <= next_cost) {
+                    continue;
+                }
+                costs.insert(next, next_cost);
+                came_from.insert(next, (position, direction));
+                open.push(Reverse((next_cost + manhattan_distance(next, target), next_cost, next)));
+            }
+        }
+        Err(PathError::Unreachable)
+    }
+}
+
+fn rebuild_path(came_from: &CameFrom, start: (i32, i32), target: (i32, i32)) -> Vec<Direction> {
+    let mut path = vec![];
+    let mut position = target;
+    while position != start {
+        let (previous, direction) = came_from[&position];
+        path.push(direction);
+        position = previous;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::ffi::Vector2;
+
+    use super::{offset, PathError, MAX_SEARCHED_TILES};
+    use crate::{
+        game_state::{Direction, MapState, Tile},
+        world::World,
+    };
+
+    /// Map with the player at the origin of a square of ground tiles, walled all around.
+    fn walled_map(radius: i32) -> MapState {
+        let mut map = MapState::empty();
+        map.world = World::new(1, false);
+        for y in -radius - 1..=radius + 1 {
+            for x in -radius - 1..=radius + 1 {
+                let border = x.abs() > radius || y.abs() > radius;
+                map.world.set_tile(x, y, if border { Tile::Wall } else { Tile::Ground });
+            }
+        }
+        map.player.position = Vector2 { x: 0.0, y: 0.0 };
+        map
+    }
+
+    fn destination(path: &[Direction]) -> (i32, i32) {
+        path.iter().fold((0, 0), |(x, y), direction| {
+            let (dx, dy) = offset(*direction);
+            (x + dx, y + dy)
+        })
+    }
+
+    #[test]
+    fn finds_the_shortest_path_around_obstacles() {
+        let mut map = walled_map(5);
+        assert_eq!(map.find_path((0, 0)).expect("Player is on the target"), vec![]);
+        let path = map.find_path((3, -2)).expect("Target is in the open");
+        assert_eq!(path.len(), 5);
+        assert_eq!(destination(&path), (3, -2));
+        for y in -5..=2 {
+            map.world.set_tile(2, y, Tile::Wall);
+        }
+        map.world.set_tile(2, 3, Tile::Lava);
+        let path = map.find_path((4, 0)).expect("Target is reachable under the wall");
+        assert_eq!(destination(&path), (4, 0));
+        assert_eq!(path.len(), 4 + 2 * 4);
+    }
+
+    #[test]
+    fn paths_to_a_hazard_only_when_it_is_the_target() {
+        let mut map = walled_map(3);
+        for y in -3..=3 {
+            map.world.set_tile(1, y, Tile::Lava);
+        }
+        assert!(matches!(map.find_path((2, 0)), Err(PathError::Unreachable)));
+        assert_eq!(map.find_path((1, 0)).expect("Lava can be walked to"), vec![Direction::Right]);
+    }
+
+    #[test]
+    fn reports_unreachable_targets() {
+        let mut map = walled_map(2);
+        assert!(matches!(map.find_path((3, 0)), Err(PathError::Unreachable)));
+        map.world.set_tile(10, 10, Tile::Ground);
+        assert!(matches!(map.find_path((10, 10)), Err(PathError::Unreachable)));
+    }
+
+    #[test]
+    fn gives_up_after_searching_too_many_tiles() {
+        // The target is walled off inside ground too large to be searched entirely
+        let radius = ((MAX_SEARCHED_TILES as f64).sqrt() / 2.0) as i32 + 2;
+        let mut map = walled_map(radius);
+        let target = (radius - 2, radius - 2);
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            map.world.set_tile(target.0 + dx, target.1 + dy, Tile::Wall);
+        }
+        assert!(matches!(map.find_path(target), Err(PathError::SearchLimitReached)));
+    }
+}
@@ -1,20 +1,29 @@
-use std::collections::HashMap;
+use std::{collections::{BTreeMap, HashMap}, sync::Mutex};
 
 use ollama_rs::Ollama;
 use raylib::{
-    ffi::Rectangle, prelude::RaylibDrawHandle, rgui::RaylibDrawGui, rstr, texture::Texture2D, RaylibHandle, RaylibThread
+    color::Color, ffi::Rectangle, prelude::{RaylibDraw, RaylibDrawHandle}, rgui::RaylibDrawGui, rstr, texture::Texture2D, RaylibHandle, RaylibThread
 };
 
-use crate::{game_state::{generate_map, Tile}, GAME_HEIGHT, GAME_WIDTH};
+use crate::{animation::Animation, game_state::{get_tile_string, MAP_STATE}, world::WorldGenerator};
 
 const AI_MODEL: &str = "llama3:latest";
 const MAP_GENERATOR_BUTTON_X: f32 = 50.0;
 const MAP_GENERATOR_BUTTON_Y: f32 = 50.0;
 const BUTTON_WIDTH: f32 = 200.0;
 const BUTTON_HEIGHT: f32 = 30.0;
+const ORE_STATISTICS_BUTTON_X: f32 = MAP_GENERATOR_BUTTON_X + BUTTON_WIDTH + 20.0;
+const REPORT_X: i32 = 50;
+const REPORT_Y: i32 = 100;
+const REPORT_LINE_HEIGHT: i32 = 25;
+const REPORT_FONT_SIZE: i32 = 20;
 
-pub fn dev_pannel_scene(rl: &mut RaylibHandle, thread: &RaylibThread, width: i32, height: i32, _textures: &HashMap<Tile, Texture2D>) {
+/// Lines shown under the buttons, describing the last generated content.
+static REPORT: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+pub fn dev_pannel_scene(rl: &mut RaylibHandle, thread: &RaylibThread, width: i32, height: i32, _textures: &HashMap<String, Texture2D>, _animations: &[Animation]) {
     let mut d: RaylibDrawHandle<'_> = rl.begin_drawing(thread);
+    d.clear_background(Color::BLACK);
     if d.gui_button(
         Rectangle {
             x: MAP_GENERATOR_BUTTON_X,
@@ -24,8 +33,58 @@ pub fn dev_pannel_scene(rl: &mut RaylibHandle, thread: &RaylibThread, width: i32
         },
         Some(rstr!("Generate Map")),
     ) {
-         println!("{:?}", generate_map(100, 100));
+        *REPORT.lock().expect("Failed to get dev pannel report") = chunk_report();
+    }
+    if d.gui_button(
+        Rectangle {
+            x: ORE_STATISTICS_BUTTON_X,
+            y: MAP_GENERATOR_BUTTON_Y,
+            width: BUTTON_WIDTH,
+            height: BUTTON_HEIGHT,
+        },
+        Some(rstr!("Ore Statistics")),
+    ) {
+        *REPORT.lock().expect("Failed to get dev pannel report") = ore_statistics_report();
+    }
+    let report = REPORT.lock().expect("Failed to get dev pannel report");
+    for (index, line) in report.iter().enumerate() {
+        d.draw_text(line, REPORT_X, REPORT_Y + index as i32 * REPORT_LINE_HEIGHT, REPORT_FONT_SIZE, Color::WHITE);
+    }
+}
+
+/// Generates the chunk at the origin of the world being played, given with --seed, and counts its tiles.
+fn chunk_report() -> Vec<String> {
+    let seed = MAP_STATE.lock().expect("Failed to get MAP STATE").world.seed;
+    let chunk = WorldGenerator::new(seed).generate_chunk((0, 0));
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for tile in chunk.tiles() {
+        *counts.entry(get_tile_string(tile)).or_default() += 1;
+    }
+    let mut report = vec![format!("Chunk (0, 0) of seed {}", seed)];
+    report.extend(counts.iter().map(|(tile, count)| format!("{} : {}", tile, count)));
+    report
+}
+
+/// Samples the ores of the world being played by distance from the spawn, to balance veins.
+fn ore_statistics_report() -> Vec<String> {
+    let map_state = MAP_STATE.lock().expect("Failed to get MAP STATE");
+    let mut report = vec![format!("Ores of seed {}", map_state.world.seed)];
+    for band in map_state.world.ore_statistics().bands {
+        let ores: Vec<String> = band
+            .ores
+            .iter()
+            .map(|(ore, count)| format!("{} {}", get_tile_string(ore), count))
+            .collect();
+        report.push(format!(
+            "{} to {} tiles : {} walls out of {} sampled, {}",
+            band.distance.start,
+            band.distance.end,
+            band.walls,
+            band.sampled_tiles,
+            ores.join(", ")
+        ));
     }
+    report
 }
 
 
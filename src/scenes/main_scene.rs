@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
 use raylib::{
     camera::Camera2D,
@@ -11,7 +11,7 @@ use raylib::{
 };
 
 use crate::{
-    animation::Animation, editor::functions::FUNCTIONS, game_state::{get_tile_string, Status, Tile, DEFAULT_ANIMATION, EDITOR_STATE, MAP_STATE}, item::TreeItem, textures::resolve_animation_index, GAME_HEIGHT, GAME_WIDTH, GET_EDITOR_STATE_ERROR, TILE_SIZE
+    animation::Animation, editor::{completion::{get_completion_details, get_signature_help, get_visible_completions, SignatureHelp}, debugger::{DEBUGGER_STATE, TOP_LEVEL_FUNCTION}, interpreter::CALL_STACK, highlight::{highlight_line, Theme, EDITOR_THEME}}, clock::DayPhase, game_state::{get_tile_string, process_chunks, process_clock, process_creatures, process_player_breaking, process_player_health, process_player_path, process_player_position, Player, EDITOR_STATE, MAP_STATE, MAX_HEALTH, MAX_HUNGER, MAX_THIRST}, item::recipe::RECIPES, textures::resolve_animation_index, GET_EDITOR_STATE_ERROR, TILE_SIZE
 };
 
 pub fn main_scene(
@@ -28,8 +28,13 @@ pub fn main_scene(
 
     d.clear_background(Color::BLACK);
 
+    process_player_path();
     process_player_position();
     process_player_breaking(dt);
+    process_player_health(dt);
+    process_clock(dt);
+    process_creatures(dt);
+    process_chunks();
     map_rendering(
         &mut d,
         x_game_anchor,
@@ -40,6 +45,7 @@ pub fn main_scene(
     );
     editor_rendering(&mut d, x_game_anchor, height, x_game_anchor);
     inventory_rendering(&mut d, x_game_anchor, width, map_textures);
+    survival_rendering(&mut d, x_game_anchor, width, height);
     d.draw_text(
         &format!("FPS : {}", d.get_fps()),
         width - 100,
@@ -47,45 +53,82 @@ pub fn main_scene(
         20,
         Color::WHITE,
     );
+    seed_rendering(&mut d, width);
+    clock_rendering(&mut d, width);
 }
 
-fn process_player_position() {
-    let mut map = MAP_STATE.lock().expect("Failed to get map state");
-    if map.player.previous_position.x < map.player.position.x {
-        map.player.previous_position.x += map.player.velocity;
-    } else if map.player.previous_position.x > map.player.position.x {
-        map.player.previous_position.x -= map.player.velocity;
-    }
-    if map.player.previous_position.y < map.player.position.y {
-        map.player.previous_position.y += map.player.velocity;
-    } else if map.player.previous_position.y > map.player.position.y {
-        map.player.previous_position.y -= map.player.velocity;
-    }
-}
+const METER_BAR_WIDTH: i32 = 200;
+const METER_BAR_HEIGHT: i32 = 20;
+const METER_FONT_SIZE: i32 = 16;
+const DEATH_FONT_SIZE: i32 = 40;
 
-fn process_player_breaking(dt: f32) {
-    let mut map = MAP_STATE.lock().expect("Failed to get map state");
-    if map.player.animation_state.status != Status::Breaking {
+/// Draws the health, hunger and thirst bars at the bottom of the game, or the death screen while the
+/// player is dead.
+fn survival_rendering(d: &mut RaylibDrawHandle<'_>, x_game_anchor: i32, width: i32, height: i32) {
+    let map_state = MAP_STATE.lock().expect("Failed to get MAP STATE");
+    let player = &map_state.player;
+    if let Some(cooldown) = player.respawn_cooldown {
+        d.draw_rectangle(x_game_anchor, 0, width - x_game_anchor, height, Color::new(0, 0, 0, 180));
+        let title = "You died";
+        let title_width = d.measure_text(title, DEATH_FONT_SIZE);
+        let center_x = x_game_anchor + (width - x_game_anchor) / 2;
+        d.draw_text(title, center_x - title_width / 2, height / 2 - DEATH_FONT_SIZE, DEATH_FONT_SIZE, Color::RED);
+        let subtitle = format!("Respawning in {:.0} s, your items lie where you fell", cooldown.max(0.0).ceil());
+        let subtitle_width = d.measure_text(&subtitle, METER_FONT_SIZE);
+        d.draw_text(&subtitle, center_x - subtitle_width / 2, height / 2 + 10, METER_FONT_SIZE, Color::WHITE);
         return;
     }
-    if map.player.animation_state.cooldown.expect("BUG - breaking cooldown has not been set") > 0.0 {
-        map.player.animation_state.cooldown = Some(map.player.animation_state.cooldown.unwrap() - dt);
-        return;
+    let meters = [
+        ("Health", player.health, MAX_HEALTH, Color::RED),
+        ("Hunger", player.hunger, MAX_HUNGER, Color::ORANGE),
+        ("Thirst", player.thirst, MAX_THIRST, Color::BLUE),
+    ];
+    let x = x_game_anchor + 20;
+    for (index, (label, value, max, color)) in meters.into_iter().enumerate() {
+        let y = height - (METER_BAR_HEIGHT + 5) * (3 - index as i32) - 15;
+        let filled_width = (METER_BAR_WIDTH as f32 * value / max) as i32;
+        d.draw_rectangle(x, y, METER_BAR_WIDTH, METER_BAR_HEIGHT, Color::DARKGRAY);
+        d.draw_rectangle(x, y, filled_width, METER_BAR_HEIGHT, color);
+        d.draw_rectangle_lines(x, y, METER_BAR_WIDTH, METER_BAR_HEIGHT, Color::WHITE);
+        let text = format!("{} {:.0} / {:.0}", label, value.ceil(), max);
+        d.draw_text(&text, x + 5, y + 2, METER_FONT_SIZE, Color::WHITE);
     }
-    let target = map
-        .player
-        .animation_state
-        .target
-        .expect("BUG - breaking target has not been set");
-    let tile = map
-        .tiles
-        .get_mut(target.y as usize)
-        .expect("BUG - invalid breaking target x has been set")
-        .get_mut(target.x as usize)
-        .expect("BUG - Invalid breaking target y has been set");
-    *tile = Tile::Ground;
-    map.spawn_item(&target, Box::new(TreeItem{}));
-    map.player.animation_state = DEFAULT_ANIMATION;
+}
+
+const CLOCK_FONT_SIZE: i32 = 20;
+const CLOCK_Y: i32 = 60;
+
+/// Shows the day, hour and phase under the seed, in the colour of the phase.
+fn clock_rendering(d: &mut RaylibDrawHandle<'_>, width: i32) {
+    let map_state = MAP_STATE.lock().expect("Failed to get MAP STATE");
+    let clock = &map_state.clock;
+    let hour = clock.hour();
+    let phase = clock.phase();
+    let text = format!(
+        "Day {} - {:02}:{:02} ({})",
+        clock.day(),
+        hour as u32,
+        (hour.fract() * 60.0) as u32,
+        phase.get_name()
+    );
+    let color = match phase {
+        DayPhase::Dawn | DayPhase::Dusk => Color::ORANGE,
+        DayPhase::Day => Color::YELLOW,
+        DayPhase::Night => Color::SKYBLUE,
+    };
+    let text_width = d.measure_text(&text, CLOCK_FONT_SIZE);
+    d.draw_text(&text, width - text_width - 10, CLOCK_Y, CLOCK_FONT_SIZE, color);
+}
+
+const SEED_FONT_SIZE: i32 = 20;
+const SEED_Y: i32 = 35;
+
+/// Shows the world seed under the FPS, so it can be shared to play or report a world again.
+fn seed_rendering(d: &mut RaylibDrawHandle<'_>, width: i32) {
+    let seed = MAP_STATE.lock().expect("Failed to get MAP STATE").world.seed;
+    let text = format!("Seed : {}", seed);
+    let text_width = d.measure_text(&text, SEED_FONT_SIZE);
+    d.draw_text(&text, width - text_width - 10, SEED_Y, SEED_FONT_SIZE, Color::WHITE);
 }
 
 const EDITOR_PROMPT_Y: i32 = 10;
@@ -95,27 +138,39 @@ const EDITOR_TEXT_X: i32 = 20;
 const EDITOR_FONT_SIZE: i32 = 20;
 const INVENTORY_FONT_SIZE: i32 = 14;
 const EDITOR_COLOR: Color = Color::WHITE;
+const EDITOR_CURSOR_WIDTH: i32 = 2;
+const EDITOR_PROMPT: &str = "> ";
+const EDITOR_COMPLETION_SELECTED_COLOR: Color = Color::DARKGOLDENROD;
 
 #[allow(static_mut_refs)]
 fn editor_rendering(d: &mut RaylibDrawHandle<'_>, x_game_anchor: i32, height: i32, width: i32) {
     let editor_state = EDITOR_STATE.lock().expect(GET_EDITOR_STATE_ERROR);
-    d.draw_rectangle(0, 0, x_game_anchor, height, Color::BLACK);
+    let theme = EDITOR_THEME.lock().expect("Failed to get editor theme");
+    d.draw_rectangle(0, 0, x_game_anchor, height, theme.background);
     d.draw_line(x_game_anchor, 0, width, height, Color::DARKGOLDENROD);
     let input_line: String = editor_state.buffer.iter().collect();
-    let input_line = "> ".to_owned() + &input_line;
-    d.draw_text(
-        &input_line,
-        EDITOR_TEXT_X,
-        EDITOR_PROMPT_Y,
-        EDITOR_FONT_SIZE,
-        EDITOR_COLOR,
-    );
+    let cursor_prefix = match &editor_state.history_search {
+        Some(search) => {
+            let search_line = format!("(reverse-i-search)`{}': {}", search.query, input_line);
+            d.draw_text(&search_line, EDITOR_TEXT_X, EDITOR_PROMPT_Y, EDITOR_FONT_SIZE, theme.plain);
+            search_line
+        }
+        None => {
+            d.draw_text(EDITOR_PROMPT, EDITOR_TEXT_X, EDITOR_PROMPT_Y, EDITOR_FONT_SIZE, theme.plain);
+            let prompt_x = EDITOR_TEXT_X + d.measure_text(EDITOR_PROMPT, EDITOR_FONT_SIZE);
+            draw_highlighted_text(d, &theme, &input_line, 0..input_line.len(), prompt_x, EDITOR_PROMPT_Y);
+            let before_cursor: String = editor_state.buffer[..editor_state.cursor].iter().collect();
+            EDITOR_PROMPT.to_owned() + &before_cursor
+        }
+    };
+    let cursor_x = EDITOR_TEXT_X + d.measure_text(&cursor_prefix, EDITOR_FONT_SIZE);
+    d.draw_rectangle(cursor_x, EDITOR_PROMPT_Y, EDITOR_CURSOR_WIDTH, EDITOR_FONT_SIZE, theme.plain);
     let mut y_history_position = EDITOR_HISTORY_Y;
     for history_text in editor_state.commands.iter().rev() {
         if y_history_position > height {
             break;
         }
-        let (text, color) = resolve_history_text_format(history_text.to_string());
+        let (text, color) = resolve_history_text_format(history_text.to_string(), &theme);
         let character_width = EDITOR_FONT_SIZE as f32 / 1.5;
         let lines = ((text.len() as i32 * character_width as i32) / width) + 1;
         let mut x_index = 0;
@@ -125,46 +180,131 @@ fn editor_rendering(d: &mut RaylibDrawHandle<'_>, x_game_anchor: i32, height: i3
             if x_index_end > text.len() as i32 {
                 x_index_end = text.len() as i32
             }
-            let text_slice = &text[x_index as usize..x_index_end as usize];
-            d.draw_text(
-                text_slice,
-                EDITOR_TEXT_X,
-                y_history_position,
-                EDITOR_FONT_SIZE,
-                color,
-            );
+            let slice_range = x_index as usize..x_index_end as usize;
+            match color {
+                Some(color) => d.draw_text(
+                    &text[slice_range],
+                    EDITOR_TEXT_X,
+                    y_history_position,
+                    EDITOR_FONT_SIZE,
+                    color,
+                ),
+                None => draw_highlighted_text(d, &theme, &text, slice_range, EDITOR_TEXT_X, y_history_position),
+            }
             x_index += line_max_width;
             y_history_position += EDITOR_HISTORY_LINE_HEIGHT;
         }
     }
-    if let Some(current_token) = get_current_token(&input_line) {
-        let completions = get_completions(&current_token);
-        let mut y_completion = EDITOR_PROMPT_Y + 30;
-        for completion in completions.iter() {
-            d.draw_rectangle(EDITOR_TEXT_X, y_completion, width, 30, Color::DARKSLATEGRAY);
-            d.draw_text(
-                completion,
-                EDITOR_TEXT_X + 5,
-                y_completion + 5,
-                EDITOR_FONT_SIZE,
-                EDITOR_COLOR,
-            );
-            y_completion += 30;
+    let (completions, selected_completion) = get_visible_completions(&editor_state);
+    let mut y_completion = EDITOR_PROMPT_Y + 30;
+    let mut selected_doc = None;
+    for (index, completion) in completions.iter().enumerate() {
+        let details = get_completion_details(completion);
+        let background = if index == selected_completion {
+            selected_doc = details.as_ref().map(|(_, doc)| doc.clone());
+            EDITOR_COMPLETION_SELECTED_COLOR
+        } else {
+            Color::DARKSLATEGRAY
+        };
+        let label = details.map_or(completion.clone(), |(signature, _)| signature);
+        d.draw_rectangle(EDITOR_TEXT_X, y_completion, width, 30, background);
+        d.draw_text(
+            &label,
+            EDITOR_TEXT_X + 5,
+            y_completion + 5,
+            EDITOR_FONT_SIZE,
+            EDITOR_COLOR,
+        );
+        y_completion += 30;
+    }
+    match selected_doc {
+        Some(doc) => doc_rendering(d, &doc, y_completion, width),
+        None => {
+            if let Some(signature_help) = get_signature_help(&editor_state) {
+                signature_help_rendering(d, &theme, &signature_help, y_completion, width);
+            }
+        }
+    }
+    debugger_rendering(d, &theme, height, width);
+}
+
+/// Draws the paused location, call stack and variables at the bottom of the editor panel.
+fn debugger_rendering(d: &mut RaylibDrawHandle<'_>, theme: &Theme, height: i32, width: i32) {
+    let debugger_state = DEBUGGER_STATE.lock().expect("Failed to get debugger state");
+    let Some(location) = &debugger_state.paused else {
+        return;
+    };
+    let call_stack = CALL_STACK.lock().expect("Failed to get call stack");
+    let header = format!("Paused in {} at line {}", location.function, location.line + 1);
+    let mut lines = vec![
+        "F11 step, F10 step over, F5 continue".to_string(),
+        "Call stack :".to_string(),
+    ];
+    lines.extend(call_stack.iter().rev().map(|frame| format!("  {}", frame.function)));
+    lines.push(format!("  {}", TOP_LEVEL_FUNCTION));
+    lines.push("Variables :".to_string());
+    if let Some(frame) = call_stack.last() {
+        lines.extend(frame.variables.iter().map(|(name, value)| format!("  {} = {}", name, value)));
+    }
+    let panel_height = (lines.len() as i32 + 2) * EDITOR_HISTORY_LINE_HEIGHT;
+    let mut y = height - panel_height;
+    d.draw_rectangle(0, y, width, panel_height, Color::DARKSLATEGRAY);
+    d.draw_text(&header, EDITOR_TEXT_X, y + 5, EDITOR_FONT_SIZE, EDITOR_COLOR);
+    y += EDITOR_HISTORY_LINE_HEIGHT;
+    draw_highlighted_text(d, theme, &location.statement, 0..location.statement.len(), EDITOR_TEXT_X, y + 5);
+    for line in lines {
+        y += EDITOR_HISTORY_LINE_HEIGHT;
+        d.draw_text(&line, EDITOR_TEXT_X, y + 5, EDITOR_FONT_SIZE, EDITOR_COLOR);
+    }
+}
+
+/// Draws the signature of the function being called, with the argument being typed highlighted.
+fn signature_help_rendering(
+    d: &mut RaylibDrawHandle<'_>,
+    theme: &Theme,
+    signature_help: &SignatureHelp,
+    y: i32,
+    width: i32,
+) {
+    d.draw_rectangle(EDITOR_TEXT_X, y, width, 30, Color::DARKSLATEGRAY);
+    let mut x = EDITOR_TEXT_X + 5;
+    let mut draw_part = |d: &mut RaylibDrawHandle<'_>, text: &str, color: Color| {
+        d.draw_text(text, x, y + 5, EDITOR_FONT_SIZE, color);
+        x += d.measure_text(text, EDITOR_FONT_SIZE);
+    };
+    draw_part(d, &format!("{}(", signature_help.name), EDITOR_COLOR);
+    for (index, argument) in signature_help.arguments.iter().enumerate() {
+        if index > 0 {
+            draw_part(d, ", ", EDITOR_COLOR);
         }
+        let color = if index == signature_help.active_argument { theme.function } else { EDITOR_COLOR };
+        draw_part(d, argument, color);
+    }
+    draw_part(d, &format!(") -> {}", signature_help.returns), EDITOR_COLOR);
+    doc_rendering(d, &signature_help.doc, y + 30, width);
+}
+
+fn doc_rendering(d: &mut RaylibDrawHandle<'_>, doc: &str, y: i32, width: i32) {
+    if doc.is_empty() {
+        return;
     }
+    d.draw_rectangle(EDITOR_TEXT_X, y, width, 30, Color::DARKSLATEGRAY);
+    d.draw_text(doc, EDITOR_TEXT_X + 5, y + 5, EDITOR_FONT_SIZE, Color::LIGHTGRAY);
 }
 
 fn inventory_rendering(d: &mut RaylibDrawHandle<'_>, x_game_anchor: i32, width: i32, textures: &HashMap<String, Texture2D>) {
     let map_state = MAP_STATE.lock().expect("Failed to get MAP STATE");
     if !map_state.is_inventory_toggled { return }
+    let crafting_x = width - CRAFTING_VIEW_WIDTH - TILE_SIZE as i32;
+    crafting_rendering(d, &map_state.player, crafting_x);
     let mut x_index = x_game_anchor + TILE_SIZE as i32;
     let mut y_index = TILE_SIZE as i32;
     for item in map_state.player.inventory.iter() {
-        let texture_name = &item.item.get_name();
+        let texture_name = &item.id;
         d.draw_rectangle(x_index, y_index, TILE_SIZE as i32, TILE_SIZE as i32 * 2, Color::WHITE);
         d.draw_texture(&textures[texture_name], x_index, y_index, Color::WHITE);
         d.draw_text(&item.number.to_string(), x_index + INVENTORY_FONT_SIZE, y_index + TILE_SIZE as i32 + INVENTORY_FONT_SIZE, INVENTORY_FONT_SIZE, Color::BLACK);
-        if (x_index + TILE_SIZE as i32) < width - TILE_SIZE as i32 {
+        if (x_index + TILE_SIZE as i32) < crafting_x - TILE_SIZE as i32 {
             x_index += TILE_SIZE as i32;
         } else {
             x_index = x_game_anchor + TILE_SIZE as i32;
@@ -173,43 +313,54 @@ fn inventory_rendering(d: &mut RaylibDrawHandle<'_>, x_game_anchor: i32, width:
     }
 }
 
+const CRAFTING_VIEW_WIDTH: i32 = 300;
+const CRAFTING_VIEW_Y: i32 = 70;
+const CRAFTING_LINE_HEIGHT: i32 = 20;
 
-fn get_completions(current_token: &str) -> Vec<String> {
-    let functions = FUNCTIONS.lock().expect("Failed to resolve functions");
-    functions
-        .iter()
-        .filter(|function| function.is_matching(current_token.to_string()))
-        .map(|function| function.to_complete_string())
-        .collect()
+/// Lists the recipes next to the inventory, the ones the inventory can craft highlighted.
+fn crafting_rendering(d: &mut RaylibDrawHandle<'_>, player: &Player, x: i32) {
+    let recipes = RECIPES.recipes();
+    let height = CRAFTING_LINE_HEIGHT * (recipes.len() as i32 + 1) + 10;
+    d.draw_rectangle(x, CRAFTING_VIEW_Y, CRAFTING_VIEW_WIDTH, height, Color::DARKSLATEGRAY);
+    d.draw_text("Crafting", x + 5, CRAFTING_VIEW_Y + 5, INVENTORY_FONT_SIZE, Color::WHITE);
+    for (index, recipe) in recipes.iter().enumerate() {
+        let craftable = player.craftable_times(&recipe.output).unwrap_or(0) > 0;
+        let color = if craftable { Color::GREEN } else { Color::GRAY };
+        let y = CRAFTING_VIEW_Y + 5 + CRAFTING_LINE_HEIGHT * (index as i32 + 1);
+        d.draw_text(&recipe.to_display_string(), x + 5, y, INVENTORY_FONT_SIZE, color);
+    }
 }
 
-fn get_current_token(input_line: &str) -> Option<String> {
-    if input_line.is_empty() {
-        return None;
-    }
-    let mut result = String::new();
-    for character in input_line.chars().rev() {
-        if character != ' ' {
-            result.push(character);
-        } else {
-            break;
+/// Draws the part of the line within the visible range, coloured by token kind.
+fn draw_highlighted_text(
+    d: &mut RaylibDrawHandle<'_>,
+    theme: &Theme,
+    line: &str,
+    visible_range: Range<usize>,
+    x: i32,
+    y: i32,
+) {
+    for (segment, kind) in highlight_line(line) {
+        let start = segment.start.max(visible_range.start);
+        let end = segment.end.min(visible_range.end);
+        if start >= end {
+            continue;
         }
+        let segment_x = x + d.measure_text(&line[visible_range.start..start], EDITOR_FONT_SIZE);
+        d.draw_text(&line[start..end], segment_x, y, EDITOR_FONT_SIZE, theme.color(kind));
     }
-    if result.is_empty() {
-        return None;
-    }
-    Some(result.chars().rev().collect())
 }
 
-fn resolve_history_text_format(history_text: String) -> (String, Color) {
+/// Results and errors keep a single colour, other lines are highlighted as code.
+fn resolve_history_text_format(history_text: String, theme: &Theme) -> (String, Option<Color>) {
     if history_text.starts_with("ERR-") {
         let text = history_text.replace("ERR-", "");
-        return (text, Color::RED);
+        return (text, Some(theme.error));
     } else if history_text.starts_with("RES-") {
         let text = history_text.replace("RES-", "");
-        return (text, Color::GREEN);
+        return (text, Some(theme.result));
     }
-    (history_text, Color::WHITE)
+    (history_text, None)
 }
 
 const TEXTURE_ERROR: &str = "Failed to resolve c_string for textures";
@@ -239,8 +390,20 @@ fn map_rendering(
         zoom: map.zoom,
     };
     let mut d = d.begin_mode2D(camera);
-    // Light source
-    let render_distance = TILE_SIZE as f32 * map.player.light_vision;
+    // Ambient light and light source, both dimmed at night
+    let (range_x, range_y) =
+        get_map_rendering_bounds(map.player.position.x, map.player.position.y);
+    let daylight = map.clock.daylight();
+    let ambient = (MAX_AMBIENT_LIGHT * daylight) as u8;
+    d.draw_rectangle(
+        range_x.start * TILE_SIZE as i32,
+        range_y.start * TILE_SIZE as i32,
+        range_x.len() as i32 * TILE_SIZE as i32,
+        range_y.len() as i32 * TILE_SIZE as i32,
+        Color::new(ambient, ambient, ambient, 255),
+    );
+    let vision_scale = NIGHT_VISION_SCALE + (1.0 - NIGHT_VISION_SCALE) * daylight;
+    let render_distance = TILE_SIZE as f32 * map.player.light_vision * vision_scale;
     d.draw_circle(
         player_x as i32 + TILE_SIZE as i32 / 2,
         player_y as i32 + TILE_SIZE as i32 / 2,
@@ -249,17 +412,13 @@ fn map_rendering(
     );
     let mut d = d.begin_blend_mode(BlendMode::BLEND_MULTIPLIED);
     // Map rendering
-    let (range_x, range_y, x_start, y_start) =
-        get_map_rendering_bounds(map.player.position.x, map.player.position.y);
-    let x_start = x_start as i32 * TILE_SIZE as i32;
-    let (mut x, mut y) = (x_start, y_start as i32 * TILE_SIZE as i32);
-    for line in map.tiles[range_y.clone()].iter() {
-        for tile in line[range_x.clone()].iter() {
-            d.draw_texture(&textures[&get_tile_string(tile)], x, y, Color::WHITE);
-            x += TILE_SIZE as i32;
+    for tile_y in range_y {
+        for tile_x in range_x.clone() {
+            if let Some((tile, biome)) = map.world.loaded_tile(tile_x, tile_y) {
+                let (x, y) = (tile_x * TILE_SIZE as i32, tile_y * TILE_SIZE as i32);
+                d.draw_texture(&textures[&get_tile_string(tile)], x, y, biome.profile().tint);
+            }
         }
-        x = x_start;
-        y += TILE_SIZE as i32;
     }
     // Items rendering
     let mut d = d.begin_blend_mode(BlendMode::BLEND_ADDITIVE);
@@ -267,14 +426,34 @@ fn map_rendering(
         let item_x = item.position.x as i32 * TILE_SIZE as i32;
         let item_y = item.position.y as i32 * TILE_SIZE as i32;
         d.draw_texture(
-            &textures[&item.item.get_name()],
+            &textures[&item.id],
             item_x,
             item_y,
             Color::WHITE,
         );
     }
-    // Player rendering
+    // Creatures and player rendering
     let mut d = d.begin_blend_mode(BlendMode::BLEND_ALPHA);
+    for creature in &map.creatures {
+        let x = creature.previous_position.x * TILE_SIZE as f32;
+        let y = creature.previous_position.y * TILE_SIZE as f32;
+        d.draw_texture_rec(
+            &textures[creature.kind.get_name()],
+            Rectangle {
+                x: (creature.current_frame * TILE_SIZE as u32) as f32,
+                y: 0.0,
+                width: TILE_SIZE as f32,
+                height: TILE_SIZE as f32,
+            },
+            Vector2 { x, y },
+            Color::WHITE,
+        );
+        let max_health = creature.kind.profile().max_health;
+        if creature.health < max_health {
+            let health_width = TILE_SIZE as f32 * creature.health / max_health;
+            d.draw_rectangle(x as i32, y as i32 - 4, health_width as i32, 3, Color::RED);
+        }
+    }
     let animation = &animations[resolve_animation_index(map.player.animation_state.status.clone())];
     d.draw_texture_rec(
         &animation.texture,
@@ -297,34 +476,16 @@ fn map_rendering(
 }
 
 const MAP_MAX_RENDER_DISTANCE: f32 = 50.0;
+/// Brightness of the tiles out of the light source during the day.
+const MAX_AMBIENT_LIGHT: f32 = 90.0;
+/// Part of the vision radius left at night.
+const NIGHT_VISION_SCALE: f32 = 0.5;
 fn get_map_rendering_bounds(
     player_x: f32,
     player_y: f32,
-) -> (std::ops::Range<usize>, std::ops::Range<usize>, usize, usize) {
-    let min_x = if player_x - MAP_MAX_RENDER_DISTANCE < 0.0 {
-        0.0
-    } else {
-        player_x - MAP_MAX_RENDER_DISTANCE
-    };
-    let max_x = if player_x + MAP_MAX_RENDER_DISTANCE > GAME_WIDTH as f32 - 1.0 {
-        GAME_WIDTH as f32 - 1.0
-    } else {
-        player_x + MAP_MAX_RENDER_DISTANCE
-    };
-    let min_y = if player_y - MAP_MAX_RENDER_DISTANCE < 0.0 {
-        0.0
-    } else {
-        player_y - MAP_MAX_RENDER_DISTANCE
-    };
-    let max_y = if player_y + MAP_MAX_RENDER_DISTANCE > GAME_HEIGHT as f32 - 1.0 {
-        GAME_HEIGHT as f32 - 1.0
-    } else {
-        player_y + MAP_MAX_RENDER_DISTANCE
-    };
+) -> (std::ops::Range<i32>, std::ops::Range<i32>) {
     (
-        min_x as usize..max_x as usize,
-        min_y as usize..max_y as usize,
-        min_x as usize,
-        min_y as usize,
+        (player_x - MAP_MAX_RENDER_DISTANCE) as i32..(player_x + MAP_MAX_RENDER_DISTANCE) as i32,
+        (player_y - MAP_MAX_RENDER_DISTANCE) as i32..(player_y + MAP_MAX_RENDER_DISTANCE) as i32,
     )
 }
@@ -4,7 +4,9 @@ use raylib::{ffi::Vector2, texture::Texture2D, RaylibHandle, RaylibThread};
 
 use crate::{
     animation::Animation,
+    creature::CreatureKind,
     game_state::{get_tile_string, Status, Tile, MAP_STATE},
+    item::registry::ITEMS,
 };
 
 pub fn load_map_texture(
@@ -68,15 +70,32 @@ pub fn load_map_texture(
             .expect("Failed to load tree texture"),
     );
     textures.insert(
-        "Pickaxe".to_string(),
-        rl.load_texture(thread, "assets/items/pickaxe.png")
-            .expect("Failed to load pickaxe texture"),
+        get_tile_string(&Tile::Bridge),
+        rl.load_texture(thread, "assets/map/bridge.png")
+            .expect("Failed to load bridge texture"),
     );
     textures.insert(
-        "TreeItem".to_string(),
-        rl.load_texture(thread, "assets/items/treeItem.png")
-            .expect("Failed to load tree item texture"),
+        get_tile_string(&Tile::SteppingStone),
+        rl.load_texture(thread, "assets/map/stepping_stone.png")
+            .expect("Failed to load stepping stone texture"),
     );
+    textures.insert(
+        CreatureKind::Wraith.get_name().to_string(),
+        rl.load_texture(thread, "assets/creatures/wraith.png")
+            .expect("Failed to load wraith texture"),
+    );
+    textures.insert(
+        CreatureKind::MagmaSlime.get_name().to_string(),
+        rl.load_texture(thread, "assets/creatures/magma_slime.png")
+            .expect("Failed to load magma slime texture"),
+    );
+    for definition in ITEMS.definitions() {
+        textures.insert(
+            definition.id.clone(),
+            rl.load_texture(thread, &definition.texture)
+                .unwrap_or_else(|_| panic!("Failed to load {} texture", definition.id)),
+        );
+    }
     textures
 }
 
@@ -0,0 +1,493 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io,
+    ops::Range,
+    path::PathBuf,
+};
+
+use noise::{core::perlin::perlin_2d, permutationtable::PermutationTable};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    biome::{Biome, BiomeProfile, BIOME_NOISE_SCALE},
+    game_state::Tile,
+};
+
+/// Width and height of a chunk, in tiles.
+pub const CHUNK_SIZE: i32 = 32;
+/// Chunks up to this distance from the chunk of the player, in chunks, are kept loaded. It covers
+/// the render distance of the map.
+pub const LOAD_RADIUS: i32 = 2;
+/// Chunks farther than this distance are evicted from the cache, and saved if modified.
+const EVICT_RADIUS: i32 = 4;
+const SAVE_DIRECTORY: &str = ".coding_survivor_world";
+/// Frequency of the terrain and tree layers, one unit of noise spanning ten tiles.
+const TERRAIN_NOISE_SCALE: f64 = 0.1;
+/// Frequency of the vein layer, walls above its threshold hold ore.
+const VEIN_NOISE_SCALE: f64 = 0.12;
+/// Frequency of the layer picking the ore of a vein, low so that a vein holds a single ore.
+const ORE_KIND_NOISE_SCALE: f64 = 0.03;
+/// Vein noise threshold next to the spawn, lowered with the distance down to
+/// VEIN_THRESHOLD - VEIN_RICHNESS_BONUS.
+const VEIN_THRESHOLD: f64 = 0.85;
+const VEIN_RICHNESS_BONUS: f64 = 0.2;
+/// Distance from the spawn, in tiles, from which veins are the richest.
+const MAX_RICHNESS_DISTANCE: f64 = 2000.0;
+/// Ores by rarity tier, with the distance from the spawn, in tiles, from which they appear.
+pub const ORE_TIERS: [(Tile, i32); 5] = [
+    (Tile::Bronze, 0),
+    (Tile::Silver, 150),
+    (Tile::Gold, 400),
+    (Tile::Mytril, 800),
+    (Tile::Demonite, 1500),
+];
+/// Width of the distance bands ore statistics are grouped by, in tiles.
+const ORE_STATISTICS_BAND: i32 = 250;
+const ORE_STATISTICS_BANDS: i32 = 8;
+/// Distance between two tiles sampled for ore statistics.
+const ORE_STATISTICS_STEP: i32 = 16;
+/// Size of the area from the origin searched for a ground tile to spawn on.
+const SPAWN_SEARCH_SIZE: i32 = 4 * CHUNK_SIZE;
+
+/// Position of a chunk, in chunks. The chunk (0, 0) holds the tiles from (0, 0) to
+/// (CHUNK_SIZE - 1, CHUNK_SIZE - 1).
+pub type ChunkPosition = (i32, i32);
+
+#[derive(Debug)]
+pub struct Chunk {
+    tiles: Vec<Tile>,
+    biomes: Vec<Biome>,
+    /// Whether a tile changed since the chunk has been generated or loaded.
+    modified: bool,
+}
+
+/// Noise layers of a world, derived from its seed. A tile only depends on these layers and its
+/// position, so chunks are the same whatever the order they are generated in.
+pub struct WorldGenerator {
+    terrain: PermutationTable,
+    trees: PermutationTable,
+    temperature: PermutationTable,
+    moisture: PermutationTable,
+    veins: PermutationTable,
+    ore_kinds: PermutationTable,
+    /// First ground tile found from the origin, row by row, if any.
+    spawn: Option<(i32, i32)>,
+}
+
+/// Ores found by sampling the generated tiles, by distance from the spawn, to balance veins.
+#[derive(Debug)]
+pub struct OreStatistics {
+    pub bands: Vec<OreBand>,
+}
+
+#[derive(Debug)]
+pub struct OreBand {
+    /// Distances from the spawn of the band, in tiles.
+    pub distance: Range<i32>,
+    pub sampled_tiles: usize,
+    pub walls: usize,
+    /// Count of each ore, in the order of ORE_TIERS.
+    pub ores: Vec<(Tile, usize)>,
+}
+
+/// Infinite map made of chunks, generated on first access and kept in a cache around the player.
+pub struct World {
+    pub seed: u64,
+    generator: Option<WorldGenerator>,
+    chunks: BTreeMap<ChunkPosition, Chunk>,
+    /// Directory modified chunks are saved in on eviction, and loaded back from instead of being
+    /// generated again. Without it, modified chunks are never evicted.
+    save_directory: Option<PathBuf>,
+}
+
+impl Chunk {
+    fn index(local_x: i32, local_y: i32) -> usize {
+        (local_y * CHUNK_SIZE + local_x) as usize
+    }
+
+    /// Tiles of the chunk, row by row.
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+}
+
+impl WorldGenerator {
+    pub fn new(seed: u64) -> WorldGenerator {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut generator = WorldGenerator {
+            terrain: PermutationTable::new(rng.random()),
+            trees: PermutationTable::new(rng.random()),
+            temperature: PermutationTable::new(rng.random()),
+            moisture: PermutationTable::new(rng.random()),
+            veins: PermutationTable::new(rng.random()),
+            ore_kinds: PermutationTable::new(rng.random()),
+            spawn: None,
+        };
+        // Ores only replace walls, so the spawn can be searched before knowing where veins are
+        generator.spawn = (0..SPAWN_SEARCH_SIZE)
+            .flat_map(|y| (0..SPAWN_SEARCH_SIZE).map(move |x| (x, y)))
+            .find(|(x, y)| generator.terrain_tile(*x, *y).0 == Tile::Ground);
+        generator
+    }
+
+    pub fn spawn(&self) -> Option<(i32, i32)> {
+        self.spawn
+    }
+
+    pub fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let mut tiles = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
+        let mut biomes = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
+        for local_y in 0..CHUNK_SIZE {
+            for local_x in 0..CHUNK_SIZE {
+                let (tile, biome) = self.generate_tile(position.0 * CHUNK_SIZE + local_x, position.1 * CHUNK_SIZE + local_y);
+                tiles.push(tile);
+                biomes.push(biome);
+            }
+        }
+        Chunk { tiles, biomes, modified: false }
+    }
+
+    /// Samples the tiles around the spawn, by bands of distance.
+    pub fn ore_statistics(&self) -> OreStatistics {
+        let mut bands: Vec<OreBand> = (0..ORE_STATISTICS_BANDS)
+            .map(|band| OreBand {
+                distance: band * ORE_STATISTICS_BAND..(band + 1) * ORE_STATISTICS_BAND,
+                sampled_tiles: 0,
+                walls: 0,
+                ores: ORE_TIERS.iter().map(|(ore, _)| (Tile::clone(ore), 0)).collect(),
+            })
+            .collect();
+        let (spawn_x, spawn_y) = self.spawn.unwrap_or_default();
+        let radius = ORE_STATISTICS_BAND * ORE_STATISTICS_BANDS;
+        for y in (spawn_y - radius..spawn_y + radius).step_by(ORE_STATISTICS_STEP as usize) {
+            for x in (spawn_x - radius..spawn_x + radius).step_by(ORE_STATISTICS_STEP as usize) {
+                let Some(band) = bands.get_mut((self.spawn_distance(x, y) as i32 / ORE_STATISTICS_BAND) as usize) else {
+                    continue;
+                };
+                band.sampled_tiles += 1;
+                let tile = self.generate_tile(x, y).0;
+                if tile == Tile::Wall {
+                    band.walls += 1;
+                }
+                if let Some((_, count)) = band.ores.iter_mut().find(|(ore, _)| *ore == tile) {
+                    *count += 1;
+                }
+            }
+        }
+        OreStatistics { bands }
+    }
+
+    fn generate_tile(&self, x: i32, y: i32) -> (Tile, Biome) {
+        match self.terrain_tile(x, y) {
+            (Tile::Wall, biome) => (self.to_wall(x, y, biome.profile()), biome),
+            generated => generated,
+        }
+    }
+
+    /// Tile before ores are placed in walls.
+    fn terrain_tile(&self, x: i32, y: i32) -> (Tile, Biome) {
+        let biome = Biome::from_climate(
+            sample_noise(&self.temperature, x, y, BIOME_NOISE_SCALE),
+            sample_noise(&self.moisture, x, y, BIOME_NOISE_SCALE),
+        );
+        let profile = biome.profile();
+        let tile = to_tile(sample_noise(&self.terrain, x, y, TERRAIN_NOISE_SCALE), profile);
+        (may_be_tree(tile, sample_noise(&self.trees, x, y, TERRAIN_NOISE_SCALE), profile), biome)
+    }
+
+    /// Walls become ore inside veins, which get larger with the distance from the spawn. The ore
+    /// of a vein is picked among the biome ores whose tier is reached at that distance.
+    fn to_wall(&self, x: i32, y: i32, profile: &BiomeProfile) -> Tile {
+        let distance = self.spawn_distance(x, y);
+        let richness = (distance / MAX_RICHNESS_DISTANCE).min(1.0);
+        let threshold = VEIN_THRESHOLD - richness * VEIN_RICHNESS_BONUS - profile.ore_richness;
+        if sample_noise(&self.veins, x, y, VEIN_NOISE_SCALE) < threshold {
+            return Tile::Wall;
+        }
+        let ores: Vec<&(Tile, u32)> = profile
+            .ores
+            .iter()
+            .filter(|(ore, _)| {
+                ORE_TIERS
+                    .iter()
+                    .any(|(tier_ore, min_distance)| tier_ore == ore && distance >= *min_distance as f64)
+            })
+            .collect();
+        let total_weight: u32 = ores.iter().map(|(_, weight)| weight).sum();
+        if total_weight == 0 {
+            return Tile::Wall;
+        }
+        // Noise is mostly within -1 and 1, mapped to a pick among the weights
+        let kind = (sample_noise(&self.ore_kinds, x, y, ORE_KIND_NOISE_SCALE) + 1.0) / 2.0;
+        let mut pick = (kind.clamp(0.0, 0.999) * total_weight as f64) as u32;
+        for (ore, weight) in ores {
+            if pick < *weight {
+                return Tile::clone(ore);
+            }
+            pick -= weight;
+        }
+        Tile::Wall
+    }
+
+    fn spawn_distance(&self, x: i32, y: i32) -> f64 {
+        let (spawn_x, spawn_y) = self.spawn.unwrap_or_default();
+        ((x - spawn_x) as f64).hypot((y - spawn_y) as f64)
+    }
+}
+
+impl World {
+    /// World with no chunk, replaced by `init_map`.
+    pub const fn empty() -> World {
+        World {
+            seed: 0,
+            generator: None,
+            chunks: BTreeMap::new(),
+            save_directory: None,
+        }
+    }
+
+    pub fn new(seed: u64, persistent: bool) -> World {
+        World {
+            seed,
+            generator: Some(WorldGenerator::new(seed)),
+            chunks: BTreeMap::new(),
+            save_directory: persistent.then(|| PathBuf::from(SAVE_DIRECTORY)),
+        }
+    }
+
+    pub fn spawn(&self) -> Option<(i32, i32)> {
+        self.generator.as_ref().and_then(WorldGenerator::spawn)
+    }
+
+    pub fn ore_statistics(&self) -> OreStatistics {
+        self.generator.as_ref().expect("BUG - world used before init_map").ore_statistics()
+    }
+
+    pub fn chunk_position(x: i32, y: i32) -> ChunkPosition {
+        (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE))
+    }
+
+    /// Tile at the position, loading or generating its chunk when needed.
+    pub fn tile(&mut self, x: i32, y: i32) -> &Tile {
+        let chunk = self.chunk(World::chunk_position(x, y));
+        &chunk.tiles[Chunk::index(x.rem_euclid(CHUNK_SIZE), y.rem_euclid(CHUNK_SIZE))]
+    }
+
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: Tile) {
+        let chunk = self.chunk(World::chunk_position(x, y));
+        chunk.tiles[Chunk::index(x.rem_euclid(CHUNK_SIZE), y.rem_euclid(CHUNK_SIZE))] = tile;
+        chunk.modified = true;
+    }
+
+    pub fn biome(&mut self, x: i32, y: i32) -> Biome {
+        let chunk = self.chunk(World::chunk_position(x, y));
+        chunk.biomes[Chunk::index(x.rem_euclid(CHUNK_SIZE), y.rem_euclid(CHUNK_SIZE))]
+    }
+
+    /// Tile and biome at the position if its chunk is loaded, for rendering without generating.
+    pub fn loaded_tile(&self, x: i32, y: i32) -> Option<(&Tile, Biome)> {
+        let chunk = self.chunks.get(&World::chunk_position(x, y))?;
+        let index = Chunk::index(x.rem_euclid(CHUNK_SIZE), y.rem_euclid(CHUNK_SIZE));
+        Some((&chunk.tiles[index], chunk.biomes[index]))
+    }
+
+    pub fn loaded_chunks_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Loads the chunks around the tile position, and evicts the far away ones.
+    pub fn stream_around(&mut self, x: i32, y: i32) {
+        let (center_x, center_y) = World::chunk_position(x, y);
+        for chunk_y in center_y - LOAD_RADIUS..=center_y + LOAD_RADIUS {
+            for chunk_x in center_x - LOAD_RADIUS..=center_x + LOAD_RADIUS {
+                self.chunk((chunk_x, chunk_y));
+            }
+        }
+        let persistent = self.save_directory.is_some();
+        let far_chunks: Vec<ChunkPosition> = self
+            .chunks
+            .iter()
+            .filter(|(position, chunk)| {
+                let distance = (position.0 - center_x).abs().max((position.1 - center_y).abs());
+                distance > EVICT_RADIUS && (persistent || !chunk.modified)
+            })
+            .map(|(position, _)| *position)
+            .collect();
+        for position in far_chunks {
+            let chunk = self.chunks.remove(&position).expect("BUG - evicted chunk is not loaded");
+            if chunk.modified {
+                if let Err(error) = self.save_chunk(position, &chunk) {
+                    eprintln!("Failed to save chunk {:?} : {:?}", position, error);
+                }
+            }
+        }
+    }
+
+    fn chunk(&mut self, position: ChunkPosition) -> &mut Chunk {
+        if !self.chunks.contains_key(&position) {
+            let generator = self.generator.as_ref().expect("BUG - world used before init_map");
+            let mut chunk = generator.generate_chunk(position);
+            if let Some(tiles) = self.load_chunk_tiles(position) {
+                chunk.tiles = tiles;
+                chunk.modified = true;
+            }
+            self.chunks.insert(position, chunk);
+        }
+        self.chunks.get_mut(&position).expect("BUG - chunk has just been loaded")
+    }
+
+    /// Saves every loaded chunk modified since it has been generated, before the game exits.
+    pub fn save_all(&self) {
+        for (position, chunk) in self.chunks.iter().filter(|(_, chunk)| chunk.modified) {
+            if let Err(error) = self.save_chunk(*position, chunk) {
+                eprintln!("Failed to save chunk {:?} : {:?}", position, error);
+            }
+        }
+    }
+
+    fn chunk_path(&self, position: ChunkPosition) -> Option<PathBuf> {
+        let save_directory = self.save_directory.as_ref()?;
+        Some(save_directory.join(self.seed.to_string()).join(format!("{}_{}.chunk", position.0, position.1)))
+    }
+
+    /// Chunks are saved as one byte per tile, biomes being generated again on load.
+    fn save_chunk(&self, position: ChunkPosition, chunk: &Chunk) -> Result<(), io::Error> {
+        let Some(path) = self.chunk_path(position) else {
+            return Ok(());
+        };
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let bytes: Vec<u8> = chunk.tiles.iter().map(|tile| Tile::clone(tile) as u8).collect();
+        fs::write(path, bytes)
+    }
+
+    fn load_chunk_tiles(&self, position: ChunkPosition) -> Option<Vec<Tile>> {
+        let bytes = fs::read(self.chunk_path(position)?).ok()?;
+        if bytes.len() != (CHUNK_SIZE * CHUNK_SIZE) as usize {
+            return None;
+        }
+        bytes.into_iter().map(Tile::from_id).collect()
+    }
+}
+
+fn sample_noise(hasher: &PermutationTable, x: i32, y: i32, scale: f64) -> f64 {
+    perlin_2d([x as f64 * scale, y as f64 * scale].into(), hasher)
+}
+
+fn may_be_tree(tile: Tile, noise_value: f64, profile: &BiomeProfile) -> Tile {
+    if noise_value < profile.tree_below && tile == Tile::Ground {
+        return Tile::Tree;
+    }
+    tile
+}
+
+fn to_tile(cell: f64, profile: &BiomeProfile) -> Tile {
+    if cell < profile.wall_below {
+        Tile::Wall
+    } else if profile.lava.as_ref().is_some_and(|lava| lava.contains(&cell)) {
+        Tile::Lava
+    } else if cell < profile.water_above {
+        Tile::Ground
+    } else {
+        Tile::Water
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use super::{Chunk, OreBand, World, WorldGenerator, CHUNK_SIZE, EVICT_RADIUS, ORE_TIERS};
+    use crate::game_state::Tile;
+
+    const SEED: u64 = 42;
+
+    /// World saving its chunks in a temporary directory of its own.
+    fn saved_world(name: &str) -> (World, PathBuf) {
+        let save_directory = env::temp_dir().join(format!("coding_survivor_{}_{}", name, std::process::id()));
+        let mut world = World::new(SEED, false);
+        world.save_directory = Some(save_directory.clone());
+        (world, save_directory)
+    }
+
+    /// Any tile other than the given one, to tell a modified tile from a generated one.
+    fn other_tile(tile: &Tile) -> Tile {
+        if *tile == Tile::Water { Tile::Lava } else { Tile::Water }
+    }
+
+    #[test]
+    fn generates_the_same_chunks_whatever_the_order() {
+        let positions = [(0, 0), (3, -2), (-5, 7), (-1, -1)];
+        let generator = WorldGenerator::new(SEED);
+        let in_order: Vec<Chunk> = positions.iter().map(|position| generator.generate_chunk(*position)).collect();
+        let generator = WorldGenerator::new(SEED);
+        let mut reversed: Vec<Chunk> = positions.iter().rev().map(|position| generator.generate_chunk(*position)).collect();
+        reversed.reverse();
+        for (chunk, other_chunk) in in_order.iter().zip(&reversed) {
+            assert_eq!(chunk.tiles, other_chunk.tiles);
+            assert_eq!(chunk.biomes, other_chunk.biomes);
+        }
+
+        let mut world = World::new(SEED, false);
+        let mut other_world = World::new(SEED, false);
+        let tiles: Vec<Tile> = (-40..40).map(|x| Tile::clone(world.tile(x, x * 3))).collect();
+        let mut other_tiles: Vec<Tile> = (-40..40).rev().map(|x| Tile::clone(other_world.tile(x, x * 3))).collect();
+        other_tiles.reverse();
+        assert_eq!(tiles, other_tiles);
+    }
+
+    #[test]
+    fn finds_rarer_and_more_ores_farther_from_the_spawn() {
+        let statistics = WorldGenerator::new(SEED).ore_statistics();
+        for band in &statistics.bands {
+            for ((ore, count), (_, tier_distance)) in band.ores.iter().zip(&ORE_TIERS) {
+                assert!(*count == 0 || band.distance.end > *tier_distance, "{:?} found in {:?}", ore, band.distance);
+            }
+        }
+        let nearest = statistics.bands.first().expect("Statistics should have bands");
+        let farthest = statistics.bands.last().expect("Statistics should have bands");
+        assert!(farthest.ores.iter().all(|(_, count)| *count > 0), "{:?}", farthest.ores);
+        let ore_density = |band: &OreBand| band.ores.iter().map(|(_, count)| count).sum::<usize>() as f64 / band.sampled_tiles as f64;
+        assert!(ore_density(farthest) > ore_density(nearest));
+    }
+
+    #[test]
+    fn finds_chunks_of_negative_positions() {
+        assert_eq!(World::chunk_position(0, 0), (0, 0));
+        assert_eq!(World::chunk_position(CHUNK_SIZE - 1, CHUNK_SIZE), (0, 1));
+        assert_eq!(World::chunk_position(-1, -1), (-1, -1));
+        assert_eq!(World::chunk_position(-CHUNK_SIZE, -CHUNK_SIZE - 1), (-1, -2));
+
+        let mut world = World::new(SEED, false);
+        let tile = other_tile(world.tile(-1, -CHUNK_SIZE));
+        world.set_tile(-1, -CHUNK_SIZE, Tile::clone(&tile));
+        let chunk = &world.chunks[&(-1, -1)];
+        assert_eq!(chunk.tiles[Chunk::index(CHUNK_SIZE - 1, 0)], tile);
+        assert_eq!(world.loaded_tile(-1, -CHUNK_SIZE).map(|(tile, _)| Tile::clone(tile)), Some(tile));
+    }
+
+    #[test]
+    fn reloads_saved_tiles_after_eviction() {
+        let (mut world, save_directory) = saved_world("eviction");
+        let tile = other_tile(world.tile(5, -3));
+        world.set_tile(5, -3, Tile::clone(&tile));
+        world.stream_around((EVICT_RADIUS + 2) * CHUNK_SIZE, 0);
+        assert!(world.loaded_tile(5, -3).is_none());
+        assert_eq!(*world.tile(5, -3), tile);
+        fs::remove_dir_all(save_directory).expect("Failed to remove test saves");
+    }
+
+    #[test]
+    fn saves_all_modified_chunks() {
+        let (mut world, save_directory) = saved_world("save_all");
+        let tile = other_tile(world.tile(-7, 2));
+        world.set_tile(-7, 2, Tile::clone(&tile));
+        world.save_all();
+        let mut reloaded_world = World::new(SEED, false);
+        reloaded_world.save_directory = Some(save_directory.clone());
+        assert_eq!(*reloaded_world.tile(-7, 2), tile);
+        fs::remove_dir_all(save_directory).expect("Failed to remove test saves");
+    }
+}